@@ -11,6 +11,7 @@ fn main() {
     runty8::debug_run::<GameState>(resources).unwrap();
 }
 
+#[derive(Clone)]
 struct GameState {
     room: Vec2<i32>,
     frames: i32,
@@ -42,6 +43,7 @@ struct GameState {
     effects: GameEffects,
 }
 
+#[derive(Clone)]
 struct GameEffects {
     shake: i32,
 }
@@ -356,6 +358,10 @@ impl App for GameState {
             }
         }
     }
+
+    fn snapshot(&self) -> Option<Self> {
+        Some(self.clone())
+    }
 }
 
 impl GameState {
@@ -403,6 +409,7 @@ impl GameState {
     }
 }
 
+#[derive(Clone)]
 struct Cloud {
     x: f32,
     y: f32,
@@ -421,6 +428,7 @@ impl Cloud {
     }
 }
 
+#[derive(Clone)]
 struct Particle {
     x: f32,
     y: f32,
@@ -442,6 +450,7 @@ impl Particle {
         }
     }
 }
+#[derive(Clone)]
 struct DeadParticle {
     x: f32,
     y: f32,
@@ -3176,3 +3185,42 @@ impl Flag {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runty8_test::TestRunner;
+
+    fn snapshot(name: &str) -> String {
+        format!(
+            "{}/celeste/snapshots/{name}.ppm",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    }
+
+    #[test]
+    fn title_screen_looks_right() {
+        let resources = runty8::load_assets!("celeste").unwrap();
+
+        TestRunner::<GameState>::new(resources)
+            .run_frames(30)
+            .assert_snapshot(snapshot("title_screen"));
+    }
+
+    #[test]
+    fn first_room_looks_right() {
+        let resources = runty8::load_assets!("celeste").unwrap();
+
+        TestRunner::<GameState>::new(resources)
+            .run_frames(10)
+            .press(K_JUMP)
+            .run_frames(2)
+            .release(K_JUMP)
+            .run_frames(100)
+            .press(Button::Right)
+            .run_frames(10)
+            .release(Button::Right)
+            .run_frames(5)
+            .assert_snapshot(snapshot("first_room"));
+    }
+}
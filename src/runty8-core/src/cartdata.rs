@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// Amount of persistent numbers each cart can store.
+pub const CARTDATA_SLOTS: usize = 64;
+
+/// Persistent values of a cart, see [`Pico8::cartdata`](crate::Pico8::cartdata).
+pub type CartdataValues = [f32; CARTDATA_SLOTS];
+
+/// Where [`Pico8::cartdata`](crate::Pico8::cartdata) values are persisted.
+pub trait CartdataStorage: Debug + Send {
+    /// Loads the values stored for `id`, if there are any.
+    fn load(&mut self, id: &str) -> Option<CartdataValues>;
+    /// Stores the values for `id`, replacing the previous ones.
+    fn store(&mut self, id: &str, values: &CartdataValues);
+}
+
+/// Keeps values in memory, so they only persist for as long as the storage lives.
+///
+/// Useful for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    carts: HashMap<String, CartdataValues>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CartdataStorage for MemoryStorage {
+    fn load(&mut self, id: &str) -> Option<CartdataValues> {
+        self.carts.get(id).copied()
+    }
+
+    fn store(&mut self, id: &str, values: &CartdataValues) {
+        self.carts.insert(id.to_owned(), *values);
+    }
+}
+
+/// Stores each cart's values in a `<id>.txt` file, in `directory`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct FileStorage {
+    directory: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// `runty8/cartdata` in the user's data directory
+    /// (e.g, `~/.local/share` in Linux, `%APPDATA%` in Windows).
+    pub fn in_data_directory() -> Self {
+        let data_directory = std::env::var_os("XDG_DATA_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| std::path::Path::new(&home).join(".local").join("share"))
+            })
+            .or_else(|| std::env::var_os("APPDATA").map(std::path::PathBuf::from))
+            .unwrap_or_default();
+
+        Self::new(data_directory.join("runty8").join("cartdata"))
+    }
+
+    fn path(&self, id: &str) -> std::path::PathBuf {
+        self.directory.join(format!("{id}.txt"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CartdataStorage for FileStorage {
+    fn load(&mut self, id: &str) -> Option<CartdataValues> {
+        let contents = std::fs::read_to_string(self.path(id)).ok()?;
+
+        Some(deserialize(&contents))
+    }
+
+    fn store(&mut self, id: &str, values: &CartdataValues) {
+        let result = std::fs::create_dir_all(&self.directory)
+            .and_then(|_| std::fs::write(self.path(id), serialize(values)));
+
+        if let Err(err) = result {
+            log::warn!("Couldn't save cartdata {id}: {err}");
+        }
+    }
+}
+
+/// Stores each cart's values in the browser's `localStorage`.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Default)]
+pub struct LocalStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    fn key(id: &str) -> String {
+        format!("runty8-cartdata-{id}")
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl CartdataStorage for LocalStorage {
+    fn load(&mut self, id: &str) -> Option<CartdataValues> {
+        let contents = Self::storage()?.get_item(&Self::key(id)).ok().flatten()?;
+
+        Some(deserialize(&contents))
+    }
+
+    fn store(&mut self, id: &str, values: &CartdataValues) {
+        let stored = Self::storage()
+            .map(|storage| storage.set_item(&Self::key(id), &serialize(values)).is_ok())
+            .unwrap_or(false);
+
+        if !stored {
+            log::error!("Couldn't save cartdata {id}");
+        }
+    }
+}
+
+/// One value per line.
+fn serialize(values: &CartdataValues) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Be lenient, missing or invalid values are 0.
+fn deserialize(contents: &str) -> CartdataValues {
+    let mut values = [0.0; CARTDATA_SLOTS];
+
+    for (value, line) in values.iter_mut().zip(contents.lines()) {
+        *value = line.trim().parse().unwrap_or(0.0);
+    }
+
+    values
+}
+
+/// The cart's persistent values, and where they're stored.
+#[derive(Debug)]
+pub(crate) struct Cartdata {
+    storage: Box<dyn CartdataStorage>,
+    id: Option<String>,
+    values: CartdataValues,
+    // Whether `values` changed since they were last stored.
+    unsaved: bool,
+}
+
+impl Cartdata {
+    pub(crate) fn new() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let storage = FileStorage::in_data_directory();
+        #[cfg(target_arch = "wasm32")]
+        let storage = LocalStorage;
+
+        Self::with_storage(storage)
+    }
+
+    pub(crate) fn with_storage(storage: impl CartdataStorage + 'static) -> Self {
+        Self {
+            storage: Box::new(storage),
+            id: None,
+            values: [0.0; CARTDATA_SLOTS],
+            unsaved: false,
+        }
+    }
+
+    pub(crate) fn open(&mut self, id: &str) -> bool {
+        assert!(
+            is_valid_id(id),
+            "Invalid cartdata id {id:?}: use up to 64 characters in a-z, 0-9 and _"
+        );
+
+        self.flush();
+        let stored_values = self.storage.load(id);
+        self.id = Some(id.to_owned());
+        self.values = stored_values.unwrap_or([0.0; CARTDATA_SLOTS]);
+
+        stored_values.is_some()
+    }
+
+    pub(crate) fn get(&self, index: i32) -> f32 {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.values.get(index))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub(crate) fn set(&mut self, index: i32, value: f32) {
+        let slot = usize::try_from(index)
+            .ok()
+            .and_then(|index| self.values.get_mut(index));
+
+        if let Some(slot) = slot {
+            *slot = value;
+            self.unsaved = self.id.is_some();
+        }
+    }
+
+    /// Stores the values set since the last flush, if any.
+    pub(crate) fn flush(&mut self) {
+        if let (true, Some(id)) = (self.unsaved, &self.id) {
+            self.storage.store(id, &self.values);
+            self.unsaved = false;
+        }
+    }
+}
+
+impl Drop for Cartdata {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, Default)]
+    struct SharedStorage {
+        storage: Arc<Mutex<MemoryStorage>>,
+        stores: Arc<Mutex<usize>>,
+    }
+
+    impl CartdataStorage for SharedStorage {
+        fn load(&mut self, id: &str) -> Option<CartdataValues> {
+            self.storage.lock().unwrap().load(id)
+        }
+
+        fn store(&mut self, id: &str, values: &CartdataValues) {
+            *self.stores.lock().unwrap() += 1;
+            self.storage.lock().unwrap().store(id, values)
+        }
+    }
+
+    #[test]
+    fn values_persist_between_sessions() {
+        let storage = SharedStorage::default();
+
+        let mut first_session = Cartdata::with_storage(storage.clone());
+        assert!(!first_session.open("my_game"));
+        first_session.set(0, 1234.0);
+        first_session.set(63, -0.5);
+        drop(first_session);
+
+        let mut second_session = Cartdata::with_storage(storage.clone());
+        assert!(second_session.open("my_game"));
+        assert_eq!(second_session.get(0), 1234.0);
+        assert_eq!(second_session.get(63), -0.5);
+        assert_eq!(second_session.get(1), 0.0);
+
+        let mut other_game = Cartdata::with_storage(storage);
+        assert!(!other_game.open("other_game"));
+        assert_eq!(other_game.get(0), 0.0);
+    }
+
+    #[test]
+    fn values_are_stored_once_per_flush() {
+        let storage = SharedStorage::default();
+        let mut cartdata = Cartdata::with_storage(storage.clone());
+        cartdata.open("my_game");
+
+        cartdata.set(0, 1.0);
+        cartdata.set(1, 2.0);
+        assert_eq!(*storage.stores.lock().unwrap(), 0);
+
+        cartdata.flush();
+        cartdata.flush();
+        assert_eq!(*storage.stores.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn out_of_range_slots_are_ignored() {
+        let mut cartdata = Cartdata::with_storage(MemoryStorage::new());
+        cartdata.open("my_game");
+
+        cartdata.set(64, 3.0);
+        cartdata.set(-1, 3.0);
+
+        assert_eq!(cartdata.get(64), 0.0);
+        assert_eq!(cartdata.get(-1), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ids_cant_be_paths() {
+        Cartdata::with_storage(MemoryStorage::new()).open("../my_game");
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn file_storage_roundtrip_works() {
+        let directory =
+            std::env::temp_dir().join(format!("runty8-cartdata-{}", std::process::id()));
+        let mut values = [0.0; CARTDATA_SLOTS];
+        values[2] = 42.25;
+
+        FileStorage::new(&directory).store("my_game", &values);
+        let loaded = FileStorage::new(&directory).load("my_game");
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(loaded, Some(values));
+    }
+}
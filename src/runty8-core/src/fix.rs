@@ -0,0 +1,470 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div, DivAssign,
+    Mul, MulAssign, Neg, Not, Rem, RemAssign, Sub, SubAssign,
+};
+
+/// Pico8's number type: a 16.16 fixed point number.
+///
+/// 16 bits for the integer part (so the range is `-32768.0..=32767.99998`)
+/// and 16 for the fractional part. Arithmetic wraps on overflow, like in pico8.
+///
+/// Use it instead of `f32` when porting carts that depend on pico8's exact arithmetic.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fix(i32);
+
+const FRACTION_BITS: u32 = 16;
+const ONE_BITS: i32 = 1 << FRACTION_BITS;
+const FRACTION_MASK: i32 = ONE_BITS - 1;
+
+impl Fix {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(ONE_BITS);
+    /// `0x7fff.ffff`, roughly 32767.99998.
+    pub const MAX: Self = Self(i32::MAX);
+    /// `0x8000.0000`, -32768.
+    pub const MIN: Self = Self(i32::MIN);
+
+    /// Creates a number from its raw 16.16 bits (e.g, `0x0001_8000` is 1.5).
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(bits)
+    }
+
+    /// The raw 16.16 bits of the number.
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    /// Converts an integer, wrapping it into `-32768..=32767`.
+    pub const fn from_i32(value: i32) -> Self {
+        Self(value.wrapping_shl(FRACTION_BITS))
+    }
+
+    /// Converts to the nearest 16.16 number, wrapping values out of range.
+    pub fn from_f32(value: f32) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    /// Converts to the nearest 16.16 number, wrapping values out of range.
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * ONE_BITS as f64).round() as i64 as i32)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    /// Exact, every 16.16 number can be represented as an `f64`.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / ONE_BITS as f64
+    }
+
+    /// The integer part, rounded down (like [`Fix::flr`]).
+    pub const fn to_i32(self) -> i32 {
+        self.0 >> FRACTION_BITS
+    }
+
+    /// Pico8's `flr`: rounds down, so `flr(-1.5)` is `-2`.
+    pub const fn flr(self) -> Self {
+        Self(self.0 & !FRACTION_MASK)
+    }
+
+    /// Pico8's `ceil`.
+    pub const fn ceil(self) -> Self {
+        Self(self.0.wrapping_add(FRACTION_MASK) & !FRACTION_MASK)
+    }
+
+    /// Pico8's `abs`. Like in pico8, `abs(-32768)` wraps back to `-32768`.
+    pub const fn abs(self) -> Self {
+        Self(self.0.wrapping_abs())
+    }
+
+    /// Pico8's `sgn`: 1 for positive numbers and zero, -1 for negative ones.
+    pub const fn sgn(self) -> Self {
+        if self.0 < 0 {
+            Self::from_i32(-1)
+        } else {
+            Self::ONE
+        }
+    }
+
+    /// Pico8's `mid`: the middle of the three values.
+    pub fn mid(self, second: Self, third: Self) -> Self {
+        self.max(second).min(self.min(second).max(third))
+    }
+
+    /// Pico8's `sqrt`, rounded down. Negative numbers return 0.
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+
+        // sqrt(bits / 2^16) * 2^16 = sqrt(bits * 2^16)
+        let radicand = (self.0 as i64) << FRACTION_BITS;
+        let mut root = (radicand as f64).sqrt() as i64;
+        // Correct any floating point error, so that the result is exactly floor(sqrt).
+        while root * root > radicand {
+            root -= 1;
+        }
+        while (root + 1) * (root + 1) <= radicand {
+            root += 1;
+        }
+
+        Self(root as i32)
+    }
+
+    /// Pico8's `sin`: takes turns instead of radians (1 is a full turn),
+    /// and is inverted to match screen coordinates (`sin(0.25)` is -1).
+    pub fn sin(self) -> Self {
+        Self::from_f64(-(self.turns() * 2.0 * PI).sin())
+    }
+
+    /// Pico8's `cos`: takes turns instead of radians (1 is a full turn).
+    pub fn cos(self) -> Self {
+        Self::from_f64((self.turns() * 2.0 * PI).cos())
+    }
+
+    /// Pico8's `atan2`: the angle of the vector (dx, dy), in turns (`0.0..1.0`),
+    /// inverted to match screen coordinates (`atan2(0, -1)` is 0.25).
+    ///
+    /// Like in pico8, `atan2(0, 0)` is 0.25.
+    pub fn atan2(dx: Self, dy: Self) -> Self {
+        if dx == Self::ZERO && dy == Self::ZERO {
+            return Self::from_bits(ONE_BITS / 4);
+        }
+
+        let turns = (-dy.to_f64()).atan2(dx.to_f64()) / (2.0 * PI);
+        let turns = Self::from_f64(turns.rem_euclid(1.0));
+
+        // Tiny negative angles can round up to a full turn.
+        if turns == Self::ONE {
+            Self::ZERO
+        } else {
+            turns
+        }
+    }
+
+    // Only the fractional part of an angle matters, keeping it small avoids losing
+    // precision for large angles.
+    fn turns(self) -> f64 {
+        (self.0 & FRACTION_MASK) as f64 / ONE_BITS as f64
+    }
+
+    /// Pico8's `shl`: shifts the bits left, `shl(1, 4)` is 16.
+    pub const fn shl(self, amount: u32) -> Self {
+        if amount >= 32 {
+            Self::ZERO
+        } else {
+            Self(self.0 << amount)
+        }
+    }
+
+    /// Pico8's `shr`: arithmetic shift right, keeping the sign.
+    pub const fn shr(self, amount: u32) -> Self {
+        if amount >= 32 {
+            Self(if self.0 < 0 { -1 } else { 0 })
+        } else {
+            Self(self.0 >> amount)
+        }
+    }
+
+    /// Pico8's `lshr`: logical shift right, shifting zeros in.
+    pub const fn lshr(self, amount: u32) -> Self {
+        if amount >= 32 {
+            Self::ZERO
+        } else {
+            Self(((self.0 as u32) >> amount) as i32)
+        }
+    }
+
+    /// Pico8's `rotl`.
+    pub const fn rotl(self, amount: u32) -> Self {
+        Self(self.0.rotate_left(amount % 32))
+    }
+
+    /// Pico8's `rotr`.
+    pub const fn rotr(self, amount: u32) -> Self {
+        Self(self.0.rotate_right(amount % 32))
+    }
+}
+
+impl From<i16> for Fix {
+    fn from(value: i16) -> Self {
+        Self::from_i32(value as i32)
+    }
+}
+
+impl From<u8> for Fix {
+    fn from(value: u8) -> Self {
+        Self::from_i32(value as i32)
+    }
+}
+
+impl From<Fix> for f32 {
+    fn from(value: Fix) -> Self {
+        value.to_f32()
+    }
+}
+
+impl From<Fix> for f64 {
+    fn from(value: Fix) -> Self {
+        value.to_f64()
+    }
+}
+
+impl Add for Fix {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl Sub for Fix {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl Mul for Fix {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self(((self.0 as i64 * rhs.0 as i64) >> FRACTION_BITS) as i32)
+    }
+}
+
+impl Div for Fix {
+    type Output = Self;
+
+    /// Like in pico8, dividing by zero returns the largest number with the sign of the dividend.
+    fn div(self, rhs: Self) -> Self {
+        if rhs.0 == 0 {
+            return if self.0 < 0 {
+                Self(i32::MIN + 1)
+            } else {
+                Self::MAX
+            };
+        }
+
+        Self((((self.0 as i64) << FRACTION_BITS) / rhs.0 as i64) as i32)
+    }
+}
+
+impl Rem for Fix {
+    type Output = Self;
+
+    /// Like in pico8, the result is never negative,
+    /// and `x % 0` is 0.
+    fn rem(self, rhs: Self) -> Self {
+        if rhs.0 == 0 {
+            return Self::ZERO;
+        }
+
+        Self((self.0 as i64).rem_euclid((rhs.0 as i64).abs()) as i32)
+    }
+}
+
+impl Neg for Fix {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.wrapping_neg())
+    }
+}
+
+impl BitAnd for Fix {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Fix {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for Fix {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for Fix {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+macro_rules! impl_assign_op {
+    ($($assign_trait:ident $assign_fn:ident => $op_fn:ident),*) => {
+        $(
+            impl $assign_trait for Fix {
+                fn $assign_fn(&mut self, rhs: Self) {
+                    *self = self.$op_fn(rhs);
+                }
+            }
+        )*
+    };
+}
+
+impl_assign_op!(
+    AddAssign add_assign => add,
+    SubAssign sub_assign => sub,
+    MulAssign mul_assign => mul,
+    DivAssign div_assign => div,
+    RemAssign rem_assign => rem,
+    BitAndAssign bitand_assign => bitand,
+    BitOrAssign bitor_assign => bitor,
+    BitXorAssign bitxor_assign => bitxor
+);
+
+/// Formats like pico8's `print`, with up to 4 decimals.
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = format!("{:.4}", self.to_f64());
+        let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+
+        match formatted {
+            "-0" => write!(f, "0"),
+            formatted => write!(f, "{formatted}"),
+        }
+    }
+}
+
+impl fmt::Debug for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fix({} = {:#010x})", self.to_f64(), self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(value: f64) -> Fix {
+        Fix::from_f64(value)
+    }
+
+    #[test]
+    fn conversions_work() {
+        assert_eq!(Fix::from_i32(3).to_bits(), 0x0003_0000);
+        assert_eq!(Fix::from(-2_i16).to_i32(), -2);
+        assert_eq!(fix(1.5).to_bits(), 0x0001_8000);
+        assert_eq!(fix(-0.5).to_f32(), -0.5);
+        assert_eq!(Fix::from_bits(1).to_f64(), 1.0 / 65536.0);
+        // Out of range integers wrap.
+        assert_eq!(Fix::from_i32(32768), Fix::MIN);
+        assert_eq!(fix(32768.0), Fix::MIN);
+    }
+
+    #[test]
+    fn arithmetic_wraps() {
+        assert_eq!(fix(1.25) + fix(2.5), fix(3.75));
+        assert_eq!(fix(1.0) - fix(2.5), fix(-1.5));
+        assert_eq!(Fix::MAX + Fix::from_bits(1), Fix::MIN);
+        assert_eq!(fix(16384.0) * fix(2.0), Fix::MIN);
+        assert_eq!(fix(-1.5) * fix(2.5), fix(-3.75));
+        assert_eq!(-Fix::MIN, Fix::MIN);
+    }
+
+    #[test]
+    fn division_works() {
+        assert_eq!(fix(7.0) / fix(2.0), fix(3.5));
+        assert_eq!(fix(1.0) / fix(3.0), Fix::from_bits(0x5555));
+        assert_eq!(fix(1.0) / Fix::ZERO, Fix::MAX);
+        assert_eq!(fix(-1.0) / Fix::ZERO, Fix::from_bits(-0x7fff_ffff));
+    }
+
+    #[test]
+    fn remainder_is_never_negative() {
+        assert_eq!(fix(7.0) % fix(3.0), fix(1.0));
+        assert_eq!(fix(-7.0) % fix(3.0), fix(2.0));
+        assert_eq!(fix(-7.0) % fix(-3.0), fix(2.0));
+        assert_eq!(fix(5.5) % fix(1.0), fix(0.5));
+        assert_eq!(fix(5.0) % Fix::ZERO, Fix::ZERO);
+    }
+
+    #[test]
+    fn flr_rounds_towards_negative_infinity() {
+        assert_eq!(fix(1.5).flr(), fix(1.0));
+        assert_eq!(fix(-1.5).flr(), fix(-2.0));
+        assert_eq!(fix(-2.0).flr(), fix(-2.0));
+        assert_eq!(fix(-1.5).to_i32(), -2);
+        assert_eq!(fix(1.25).ceil(), fix(2.0));
+        assert_eq!(fix(-1.25).ceil(), fix(-1.0));
+    }
+
+    #[test]
+    fn sgn_abs_and_mid_work() {
+        assert_eq!(fix(-3.0).sgn(), fix(-1.0));
+        assert_eq!(Fix::ZERO.sgn(), fix(1.0));
+        assert_eq!(fix(-3.5).abs(), fix(3.5));
+        assert_eq!(fix(8.0).mid(fix(2.0), fix(4.0)), fix(4.0));
+        assert_eq!(fix(-3.5).mid(fix(-3.4), fix(-3.6)), fix(-3.5));
+    }
+
+    #[test]
+    fn bitwise_operations_work() {
+        assert_eq!(fix(0.5) | fix(2.0), fix(2.5));
+        assert_eq!(fix(3.75) & fix(1.5), fix(1.5));
+        assert_eq!(fix(3.0) ^ fix(1.0), fix(2.0));
+        assert_eq!(!Fix::ZERO, Fix::from_bits(-1));
+        assert_eq!(fix(1.0).shl(4), fix(16.0));
+        assert_eq!(fix(-16.0).shr(2), fix(-4.0));
+        assert_eq!(fix(-1.0).lshr(16), Fix::from_bits(0xffff));
+        assert_eq!(fix(0.5).rotl(16), Fix::from_bits(0x8000_0000_u32 as i32));
+        assert_eq!(fix(1.0).rotr(1), fix(0.5));
+        assert_eq!(fix(1.0).rotr(17), Fix::MIN);
+        assert_eq!(fix(-1.0).shr(40), Fix::from_bits(-1));
+    }
+
+    #[test]
+    fn sqrt_works() {
+        assert_eq!(fix(16.0).sqrt(), fix(4.0));
+        assert_eq!(fix(2.0).sqrt(), Fix::from_bits(0x0001_6a09));
+        assert_eq!(fix(-4.0).sqrt(), Fix::ZERO);
+        assert_eq!(Fix::MAX.sqrt().to_i32(), 181);
+    }
+
+    #[test]
+    fn trigonometry_uses_turns() {
+        assert_eq!(fix(0.0).sin(), fix(0.0));
+        assert_eq!(fix(0.25).sin(), fix(-1.0));
+        assert_eq!(fix(0.75).sin(), fix(1.0));
+        assert_eq!(fix(0.0).cos(), fix(1.0));
+        assert_eq!(fix(0.5).cos(), fix(-1.0));
+        assert_eq!(fix(1.25).sin(), fix(0.25).sin());
+        assert_eq!(fix(-0.25).sin(), fix(1.0));
+    }
+
+    #[test]
+    fn atan2_works() {
+        assert_eq!(Fix::atan2(fix(1.0), fix(0.0)), fix(0.0));
+        assert_eq!(Fix::atan2(fix(0.0), fix(-1.0)), fix(0.25));
+        assert_eq!(Fix::atan2(fix(-1.0), fix(0.0)), fix(0.5));
+        assert_eq!(Fix::atan2(fix(0.0), fix(1.0)), fix(0.75));
+        assert_eq!(Fix::atan2(fix(1.0), fix(-1.0)), fix(0.125));
+        assert_eq!(Fix::atan2(fix(0.0), fix(0.0)), fix(0.25));
+    }
+
+    #[test]
+    fn display_matches_pico8() {
+        assert_eq!(fix(1.5).to_string(), "1.5");
+        assert_eq!(fix(-3.0).to_string(), "-3");
+        assert_eq!((fix(1.0) / fix(3.0)).to_string(), "0.3333");
+        assert_eq!(Fix::from_bits(-1).to_string(), "0");
+    }
+}
@@ -0,0 +1,150 @@
+use crate::draw_data::COLORS;
+use crate::Color;
+
+/// How [`SpriteSheet::import_png`](crate::SpriteSheet::import_png) converts an image's pixels
+/// to pico8 colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Spreads the difference between each pixel and its nearest pico8 color to the next pixels
+    /// (Floyd-Steinberg dithering), to approximate colors that aren't in the palette.
+    pub dither: bool,
+    /// Imports (mostly) transparent pixels as transparent (0), instead of by their color.
+    pub alpha_as_transparent: bool,
+}
+
+/// An image with its pixels as RGBA, row by row.
+#[derive(Debug)]
+pub(crate) struct Image {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    pub(crate) fn from_png(png: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(png);
+        // Palettes become RGB, 16 bit channels become 8 bit, and so on.
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder
+            .read_info()
+            .map_err(|err| format!("[Import] Couldn't read PNG: {err}"))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .map_err(|err| format!("[Import] Couldn't decode PNG: {err}"))?;
+        let bytes = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => bytes
+                .chunks_exact(4)
+                .map(|rgba| [rgba[0], rgba[1], rgba[2], rgba[3]])
+                .collect(),
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .map(|gray_alpha| [gray_alpha[0], gray_alpha[0], gray_alpha[0], gray_alpha[1]])
+                .collect(),
+            png::ColorType::Grayscale => {
+                bytes.iter().map(|&gray| [gray, gray, gray, 255]).collect()
+            }
+            png::ColorType::Indexed => {
+                return Err("[Import] Unexpected indexed PNG after decoding".to_owned())
+            }
+        };
+
+        Ok(Self {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        })
+    }
+
+    /// The nearest pico8 color to each pixel, row by row.
+    pub(crate) fn to_pico8(&self, options: ImportOptions) -> Vec<Color> {
+        // Dithering error carried over to each pixel, per channel.
+        let mut errors = vec![[0; 3]; self.pixels.len()];
+        let mut colors = Vec::with_capacity(self.pixels.len());
+
+        for (index, &[r, g, b, a]) in self.pixels.iter().enumerate() {
+            if options.alpha_as_transparent && a < 128 {
+                colors.push(0);
+                continue;
+            }
+
+            let pixel = [r, g, b];
+            let wanted = [0, 1, 2]
+                .map(|channel| (pixel[channel] as i32 + errors[index][channel]).clamp(0, 255));
+            let color = nearest_color(wanted);
+            colors.push(color);
+
+            if options.dither {
+                let actual = rgb(color);
+                let (x, y) = (index % self.width, index / self.width);
+                let neighbours = [
+                    (x + 1, y, 7),
+                    (x.wrapping_sub(1), y + 1, 3),
+                    (x, y + 1, 5),
+                    (x + 1, y + 1, 1),
+                ];
+
+                for (x, y, weight) in neighbours {
+                    if x < self.width && y < self.height {
+                        let neighbour = &mut errors[x + y * self.width];
+
+                        for channel in 0..3 {
+                            neighbour[channel] += (wanted[channel] - actual[channel]) * weight / 16;
+                        }
+                    }
+                }
+            }
+        }
+
+        colors
+    }
+}
+
+/// The pico8 color closest to `wanted` (as RGB).
+fn nearest_color(wanted: [i32; 3]) -> Color {
+    (0..COLORS.len() as Color)
+        .min_by_key(|&color| {
+            let actual = rgb(color);
+
+            (0..3)
+                .map(|channel| (actual[channel] - wanted[channel]).pow(2))
+                .sum::<i32>()
+        })
+        .unwrap()
+}
+
+fn rgb(color: Color) -> [i32; 3] {
+    let c = COLORS[color as usize];
+
+    [(c >> 16) & 0xFF, (c >> 8) & 0xFF, c & 0xFF].map(|channel| channel as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dithering_mixes_colors_to_approximate_the_missing_ones() {
+        // Between black and dark blue, a bit closer to black.
+        let image = Image {
+            width: 8,
+            height: 1,
+            pixels: vec![[14, 21, 41, 255]; 8],
+        };
+
+        assert_eq!(image.to_pico8(ImportOptions::default()), [0; 8]);
+
+        let dithered = image.to_pico8(ImportOptions {
+            dither: true,
+            alpha_as_transparent: false,
+        });
+        assert!(dithered.contains(&0));
+        assert!(dithered.contains(&1));
+    }
+}
@@ -9,8 +9,19 @@ pub struct Input {
     pub(crate) x: Option<bool>,
     pub(crate) c: Option<bool>,
     pub mouse: Option<bool>,
+    pub(crate) mouse_right: Option<bool>,
+    pub(crate) mouse_middle: Option<bool>,
     pub mouse_x: i32,
     pub mouse_y: i32,
+    // Total amount scrolled since the start, so that
+    // [`State`](crate::state::State) can compute per-frame deltas.
+    pub(crate) wheel_x: i32,
+    pub(crate) wheel_y: i32,
+    // Same for the devkit keyboard: how many characters have been typed so far, and the last one.
+    pub(crate) typed_chars: u32,
+    pub(crate) last_typed_char: Option<char>,
+    // Enter or P, which open and close the pause menu.
+    pub(crate) pause: Option<bool>,
 }
 
 #[allow(clippy::new_without_default)]
@@ -24,9 +35,16 @@ impl Input {
             x: None,
             c: None,
             mouse: None,
+            mouse_right: None,
+            mouse_middle: None,
             // TODO: Initialize mouse properly
             mouse_x: 64,
             mouse_y: 64,
+            wheel_x: 0,
+            wheel_y: 0,
+            typed_chars: 0,
+            last_typed_char: None,
+            pause: None,
         }
     }
 
@@ -37,19 +55,27 @@ impl Input {
                     let key_ref = self.button_to_ref(button);
                     *key_ref = Some(state == KeyState::Down);
                 }
+
+                if matches!(key, Key::Enter | Key::P) {
+                    self.pause = Some(state == KeyState::Down);
+                }
+
+                if let (KeyState::Down, Some(typed_char)) = (state, key_to_char(key)) {
+                    self.typed_chars += 1;
+                    self.last_typed_char = Some(typed_char);
+                }
             }
-            InputEvent::Mouse(MouseEvent::Button {
-                button: MouseButton::Left,
-                state,
-            }) => {
-                self.mouse = Some(state == KeyState::Down);
+            InputEvent::Mouse(MouseEvent::Button { button, state }) => {
+                let button_ref = self.button_to_ref(mouse_button_to_button(button));
+                *button_ref = Some(state == KeyState::Down);
             }
             InputEvent::Mouse(MouseEvent::Move { x, y }) => {
                 self.mouse_x = x;
                 self.mouse_y = y;
             }
-            InputEvent::Mouse(MouseEvent::Button { .. }) => {
-                // Runty8 games currently can't access other mouse buttons
+            InputEvent::Mouse(MouseEvent::Wheel { delta_x, delta_y }) => {
+                self.wheel_x += delta_x;
+                self.wheel_y += delta_y;
             }
         }
     }
@@ -63,6 +89,8 @@ impl Input {
             Button::Up => &mut self.up,
             Button::Down => &mut self.down,
             Button::Mouse => &mut self.mouse,
+            Button::MouseRight => &mut self.mouse_right,
+            Button::MouseMiddle => &mut self.mouse_middle,
         }
     }
 }
@@ -78,3 +106,48 @@ fn key_to_button(key: Key) -> Option<Button> {
         _ => None,
     }
 }
+
+fn key_to_char(key: Key) -> Option<char> {
+    let c = match key {
+        Key::A => 'a',
+        Key::B => 'b',
+        Key::C => 'c',
+        Key::D => 'd',
+        Key::E => 'e',
+        Key::F => 'f',
+        Key::G => 'g',
+        Key::H => 'h',
+        Key::I => 'i',
+        Key::J => 'j',
+        Key::K => 'k',
+        Key::L => 'l',
+        Key::M => 'm',
+        Key::N => 'n',
+        Key::O => 'o',
+        Key::P => 'p',
+        Key::Q => 'q',
+        Key::R => 'r',
+        Key::S => 's',
+        Key::T => 't',
+        Key::U => 'u',
+        Key::V => 'v',
+        Key::W => 'w',
+        Key::X => 'x',
+        Key::Y => 'y',
+        Key::Z => 'z',
+        Key::Space => ' ',
+        Key::Minus => '-',
+        Key::Equals => '=',
+        _ => return None,
+    };
+
+    Some(c)
+}
+
+fn mouse_button_to_button(mouse_button: MouseButton) -> Button {
+    match mouse_button {
+        MouseButton::Left => Button::Mouse,
+        MouseButton::Right => Button::MouseRight,
+        MouseButton::Middle => Button::MouseMiddle,
+    }
+}
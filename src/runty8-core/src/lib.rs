@@ -2,30 +2,67 @@
 
 //! Types and functions required to run a Runty8 game.
 
+mod cartdata;
 mod draw_data;
+mod fix;
 mod flags;
+#[cfg(feature = "png-import")]
+mod image_import;
 mod input;
 mod map;
+mod pause_menu;
 mod pico8;
+mod recording;
 pub mod serialize;
 mod sprite_sheet;
+mod stat;
 mod state;
+mod timestep;
 pub use draw_data::colors;
 
 pub mod draw;
 pub mod font;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use cartdata::FileStorage;
+#[cfg(target_arch = "wasm32")]
+pub use cartdata::LocalStorage;
+pub use cartdata::{CartdataStorage, CartdataValues, MemoryStorage, CARTDATA_SLOTS};
+pub use fix::Fix;
 pub use flags::Flags;
+#[cfg(feature = "png-import")]
+pub use image_import::ImportOptions;
 pub use input::Input;
 pub use map::Map;
+pub use pause_menu::{MenuAction, MAX_MENU_ITEMS};
 pub use pico8::*;
+pub use recording::Recording;
 pub use sprite_sheet::{Sprite, SpriteSheet};
+pub use stat::{Stat, TimeField};
+pub use timestep::{FixedTimestep, Fps};
 
 /// A regular pico8 app.
 pub trait App {
+    /// How many times per second `update` and `draw` are called.
+    const FPS: Fps = Fps::Thirty;
+
     fn init(pico8: &mut Pico8) -> Self;
     fn update(&mut self, pico8: &mut Pico8);
     fn draw(&mut self, pico8: &mut Pico8);
+
+    /// Called when the player selects an item added with [`Pico8::menuitem`] in the pause menu.
+    fn menuitem_selected(&mut self, _index: u8, _pico8: &mut Pico8) {}
+
+    /// A copy of the game's state, for save states.
+    ///
+    /// Save states are opt-in: games that implement `Clone` can enable them
+    /// by returning `Some(self.clone())`.
+    fn snapshot(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 /// A pico8 color.
@@ -50,11 +87,15 @@ pub enum Button {
     C,
     /// Left mouse button.
     Mouse,
+    /// Right mouse button.
+    MouseRight,
+    /// Middle mouse button.
+    MouseMiddle,
 }
 
 /// Game assets: sprite sheet, map, flags.
 // TODO: Rename to assets?
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Resources {
     pub assets_path: String,
     pub sprite_sheet: SpriteSheet,
@@ -62,6 +103,27 @@ pub struct Resources {
     pub map: Map,
 }
 
+impl Resources {
+    /// Blank sprite sheet, flags and map, loaded from (and saved to) `assets_path`.
+    pub fn empty(assets_path: impl Into<String>) -> Self {
+        Self {
+            assets_path: assets_path.into(),
+            sprite_sheet: SpriteSheet::new(),
+            sprite_flags: Flags::new(),
+            map: Map::new(),
+        }
+    }
+
+    /// Like [`Resources::empty`], with the assets in a `name` directory (not created here)
+    /// inside the system's temporary directory, unique to this process. Handy for tests.
+    pub fn empty_in_temp_dir(name: &str) -> Self {
+        let assets_path =
+            std::env::temp_dir().join(format!("runty8-{name}-{}", std::process::id()));
+
+        Self::empty(assets_path.to_string_lossy())
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// Key state: up or down.
 pub enum KeyState {
@@ -139,6 +201,12 @@ pub enum Key {
     Alt,
     ///
     Space,
+    /// Enter (return) key.
+    Enter,
+    /// Minus (-) key.
+    Minus,
+    /// Equals (=) key, which is also plus (+) in most layouts.
+    Equals,
 }
 
 /// Keyboard event (key up/down).
@@ -170,7 +238,7 @@ pub enum MouseButton {
     Right,
 }
 
-/// Mouse events (mouse move, button presses).
+/// Mouse events (mouse move, button presses, wheel).
 #[derive(Clone, Copy, Debug)]
 pub enum MouseEvent {
     /// Mouse move event.
@@ -188,6 +256,13 @@ pub enum MouseEvent {
         /// Whether the button was pressed or released.
         state: KeyState,
     },
+    /// Mouse wheel scrolled.
+    Wheel {
+        /// Horizontal scroll, positive when scrolling right.
+        delta_x: i32,
+        /// Vertical scroll, positive when scrolling up.
+        delta_y: i32,
+    },
 }
 
 /// Runty8 events (input, tick, etc).
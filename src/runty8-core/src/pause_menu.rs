@@ -0,0 +1,206 @@
+use crate::draw_data::DrawData;
+use crate::state::State;
+use crate::{colors, Button};
+
+/// How many custom items can be added with [`Pico8::menuitem`](crate::Pico8::menuitem).
+pub const MAX_MENU_ITEMS: u8 = 5;
+
+/// What the player chose in the pause menu, see [`Pico8::update_pause_menu`](crate::Pico8::update_pause_menu).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    /// Go back to the game.
+    Continue,
+    /// A custom item, added with [`Pico8::menuitem`](crate::Pico8::menuitem).
+    MenuItem(u8),
+    /// Restart the game.
+    Reset,
+}
+
+/// Pico8's pause menu: "continue", up to 5 custom items and "reset cart".
+#[derive(Clone, Debug)]
+pub(crate) struct PauseMenu {
+    pub(crate) enabled: bool,
+    items: [Option<String>; MAX_MENU_ITEMS as usize],
+    open: Option<OpenMenu>,
+}
+
+#[derive(Clone, Debug)]
+struct OpenMenu {
+    // What the game had drawn when it was paused, the menu is drawn on top of it.
+    game_frame: Box<DrawData>,
+    selected: usize,
+}
+
+impl PauseMenu {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: true,
+            items: Default::default(),
+            open: None,
+        }
+    }
+
+    pub(crate) fn set_item(&mut self, index: u8, label: Option<&str>) {
+        assert!(
+            (1..=MAX_MENU_ITEMS).contains(&index),
+            "Menu items go from 1 to {MAX_MENU_ITEMS}, got {index}"
+        );
+
+        self.items[index as usize - 1] = label.map(str::to_owned);
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    pub(crate) fn open(&mut self, draw_data: &DrawData) {
+        if self.enabled && self.open.is_none() {
+            self.open = Some(OpenMenu {
+                game_frame: Box::new(draw_data.clone()),
+                selected: 0,
+            });
+        }
+    }
+
+    /// Closes the menu, restoring the game's frame (and draw state).
+    pub(crate) fn close(&mut self, draw_data: &mut DrawData) {
+        if let Some(open_menu) = self.open.take() {
+            *draw_data = *open_menu.game_frame;
+        }
+    }
+
+    fn entries(&self) -> Vec<(&str, MenuAction)> {
+        let items =
+            self.items.iter().zip(1..).filter_map(|(label, index)| {
+                Some((label.as_deref()?, MenuAction::MenuItem(index)))
+            });
+
+        std::iter::once(("CONTINUE", MenuAction::Continue))
+            .chain(items)
+            .chain(std::iter::once(("RESET CART", MenuAction::Reset)))
+            .collect()
+    }
+
+    pub(crate) fn update(&mut self, state: &State, draw_data: &mut DrawData) -> Option<MenuAction> {
+        let entry_count = self.entries().len();
+        let open_menu = self.open.as_mut()?;
+
+        if state.button(Button::Up).btnp() {
+            open_menu.selected = (open_menu.selected + entry_count - 1) % entry_count;
+        }
+        if state.button(Button::Down).btnp() {
+            open_menu.selected = (open_menu.selected + 1) % entry_count;
+        }
+
+        let confirmed = state.button(Button::X).btnp() || state.button(Button::C).btnp();
+        if !confirmed {
+            return None;
+        }
+
+        let selected = open_menu.selected;
+        let (_, action) = self.entries()[selected];
+        self.close(draw_data);
+
+        Some(action)
+    }
+
+    pub(crate) fn draw(&self, draw_data: &mut DrawData) {
+        let open_menu = match &self.open {
+            Some(open_menu) => open_menu,
+            None => return,
+        };
+
+        *draw_data = (*open_menu.game_frame).clone();
+        draw_data.camera(0, 0);
+        draw_data.reset_pal();
+
+        let entries = self.entries();
+        let longest_label = entries
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0) as i32;
+
+        let width = longest_label * 4 + 16;
+        let height = entries.len() as i32 * 8 + 6;
+        let x0 = 64 - width / 2;
+        let y0 = 64 - height / 2;
+
+        draw_data.rectfill(x0, y0, x0 + width - 1, y0 + height - 1, colors::BLACK);
+        draw_data.rect(x0, y0, x0 + width - 1, y0 + height - 1, colors::WHITE);
+
+        for (index, (label, _)) in entries.iter().enumerate() {
+            let y = y0 + 4 + index as i32 * 8;
+
+            if index == open_menu.selected {
+                draw_data.print(">", x0 + 4, y, colors::WHITE);
+            }
+            draw_data.print(label, x0 + 10, y, colors::WHITE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Input, InputEvent, Key, KeyState, KeyboardEvent};
+
+    // Presses `key` during a single frame.
+    fn tap(menu: &mut PauseMenu, key: Key) -> Option<MenuAction> {
+        let mut input = Input::new();
+        let mut state = State::new();
+        let mut draw_data = DrawData::new();
+
+        input.on_event(InputEvent::Keyboard(KeyboardEvent {
+            key,
+            state: KeyState::Down,
+        }));
+        state.update_input(&input);
+
+        menu.update(&state, &mut draw_data)
+    }
+
+    #[test]
+    fn entries_include_custom_items() {
+        let mut menu = PauseMenu::new();
+        menu.set_item(2, Some("MUSIC OFF"));
+        menu.set_item(4, Some("RETRY"));
+        menu.set_item(4, None);
+
+        assert_eq!(
+            menu.entries(),
+            [
+                ("CONTINUE", MenuAction::Continue),
+                ("MUSIC OFF", MenuAction::MenuItem(2)),
+                ("RESET CART", MenuAction::Reset)
+            ]
+        );
+    }
+
+    #[test]
+    fn selecting_an_entry_closes_the_menu() {
+        let mut menu = PauseMenu::new();
+        menu.set_item(1, Some("MUSIC OFF"));
+        menu.open(&DrawData::new());
+
+        assert_eq!(tap(&mut menu, Key::UpArrow), None);
+        assert!(menu.is_open());
+        // Wraps around, from the first entry to the last.
+        assert_eq!(tap(&mut menu, Key::X), Some(MenuAction::Reset));
+        assert!(!menu.is_open());
+
+        menu.open(&DrawData::new());
+        tap(&mut menu, Key::DownArrow);
+        assert_eq!(tap(&mut menu, Key::C), Some(MenuAction::MenuItem(1)));
+    }
+
+    #[test]
+    fn disabled_menus_dont_open() {
+        let mut menu = PauseMenu::new();
+        menu.enabled = false;
+
+        menu.open(&DrawData::new());
+
+        assert!(!menu.is_open());
+    }
+}
@@ -1,10 +1,15 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cell::RefCell;
 use std::f32::consts::PI;
 
+use crate::cartdata::{Cartdata, CartdataStorage};
 use crate::draw_data::DrawData;
+use crate::pause_menu::{MenuAction, PauseMenu};
 use crate::sprite_sheet::Sprite;
+use crate::stat::{FrameStats, Stat};
 use crate::state::State;
-use crate::{Button, Color, Resources};
+use crate::{Button, Color, Fps, Resources};
 
 /// Struct providing an implementation of the pico8 API.
 #[derive(Debug)]
@@ -13,6 +18,9 @@ pub struct Pico8 {
     pub state: State,
     pub resources: Resources,
     new_title: Option<String>,
+    cartdata: Cartdata,
+    frame_stats: FrameStats,
+    pause_menu: PauseMenu,
 }
 
 impl Pico8 {
@@ -22,8 +30,55 @@ impl Pico8 {
             state: State::new(),
             resources,
             new_title: None,
+            cartdata: Cartdata::new(),
+            frame_stats: FrameStats::new(),
+            pause_menu: PauseMenu::new(),
         }
     }
+
+    /// Changes where [`Pico8::cartdata`] values are persisted
+    /// (by default, a file on native and `localStorage` on wasm).
+    pub fn set_cartdata_storage(&mut self, storage: impl CartdataStorage + 'static) {
+        self.cartdata = Cartdata::with_storage(storage);
+    }
+}
+
+/// A copy of the runtime state of [`Pico8`] (screen, input, time, pause menu
+/// and `rnd`'s generator). See [`Pico8::snapshot`].
+#[derive(Clone, Debug)]
+pub struct Pico8Snapshot {
+    resources: Resources,
+    draw_data: DrawData,
+    state: State,
+    frame_stats: FrameStats,
+    pause_menu: PauseMenu,
+    rng: ChaCha8Rng,
+}
+
+impl Pico8 {
+    /// Copies the game's state (resources, screen, input, time and random number generator),
+    /// so that it can be restored later with [`Pico8::restore`].
+    ///
+    /// Cartdata isn't included, since it's meant to outlive the game's state.
+    pub fn snapshot(&self) -> Pico8Snapshot {
+        Pico8Snapshot {
+            resources: self.resources.clone(),
+            draw_data: self.draw_data.clone(),
+            state: self.state.clone(),
+            frame_stats: self.frame_stats.clone(),
+            pause_menu: self.pause_menu.clone(),
+            rng: RNG.with(|rng| rng.borrow().clone()),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Pico8Snapshot) {
+        self.resources = snapshot.resources.clone();
+        self.draw_data = snapshot.draw_data.clone();
+        self.state = snapshot.state.clone();
+        self.frame_stats = snapshot.frame_stats.clone();
+        self.pause_menu = snapshot.pause_menu.clone();
+        RNG.with(|rng| *rng.borrow_mut() = snapshot.rng.clone());
+    }
 }
 
 // Public (Pico8) interface
@@ -54,6 +109,27 @@ impl Pico8 {
         self.resources.sprite_flags.fset_all(sprite, flags);
     }
 
+    /// Pico8's [`cartdata`](<https://pico-8.fandom.com/wiki/Cartdata>) function.
+    ///
+    /// Loads the 64 persistent values stored for `id` (up to 64 characters in `a-z`, `0-9` and `_`),
+    /// returning whether there were any.
+    pub fn cartdata(&mut self, id: &str) -> bool {
+        self.cartdata.open(id)
+    }
+
+    /// Pico8's [`dget`](<https://pico-8.fandom.com/wiki/Dget>) function.
+    pub fn dget(&self, index: i32) -> f32 {
+        self.cartdata.get(index)
+    }
+
+    /// Pico8's [`dset`](<https://pico-8.fandom.com/wiki/Dset>) function.
+    ///
+    /// Values are only persisted after calling [`Pico8::cartdata`],
+    /// once per tick (see [`Pico8::record_tick`]) and when `Pico8` is dropped.
+    pub fn dset(&mut self, index: i32, value: f32) {
+        self.cartdata.set(index, value);
+    }
+
     pub fn btnp(&self, button: Button) -> bool {
         self.state.button(button).btnp()
     }
@@ -170,6 +246,66 @@ impl Pico8 {
         todo!()
     }
 
+    /// Pico8's [`menuitem`](<https://pico-8.fandom.com/wiki/Menuitem>) function.
+    ///
+    /// Adds (or removes, with `None`) item `index` (1 to 5) to the pause menu.
+    /// When the player selects it, [`App::menuitem_selected`](crate::App::menuitem_selected) is called.
+    pub fn menuitem(&mut self, index: u8, label: Option<&str>) {
+        self.pause_menu.set_item(index, label);
+    }
+
+    /// Enables or disables the pause menu, like `poke(0x5f30, 1)` in pico8.
+    pub fn set_pause_menu_enabled(&mut self, enabled: bool) {
+        self.pause_menu.enabled = enabled;
+    }
+
+    /// Pico8's [`time`](<https://pico-8.fandom.com/wiki/Time>) function: seconds since the game started.
+    ///
+    /// Based on the amount of updates run rather than on the wall clock,
+    /// so it's deterministic and stops while the game isn't running (e.g, in the editor).
+    pub fn time(&self) -> f32 {
+        (self.frame_stats.elapsed_millis / 1000.0) as f32
+    }
+
+    /// Pico8's `t`, same as [`Pico8::time`].
+    pub fn t(&self) -> f32 {
+        self.time()
+    }
+
+    /// How many times the game has been updated since it started.
+    pub fn frame_count(&self) -> u32 {
+        self.frame_stats.frames
+    }
+
+    /// Pico8's [`stat`](<https://pico-8.fandom.com/wiki/Stat>) function.
+    ///
+    /// See [`Stat`] for the supported queries. The rest return 0, like unknown ones in pico8.
+    pub fn stat(&self, n: i32) -> f32 {
+        Stat::from_index(n).map_or(0.0, |stat| self.get_stat(stat))
+    }
+
+    /// Typed version of [`Pico8::stat`].
+    pub fn get_stat(&self, stat: Stat) -> f32 {
+        let bool_to_f32 = |b: bool| if b { 1.0 } else { 0.0 };
+
+        match stat {
+            Stat::CpuUsage | Stat::SystemCpuUsage => self.frame_stats.cpu_usage,
+            Stat::Fps | Stat::HostFps => self.frame_stats.fps(),
+            Stat::TargetFps => self.frame_stats.target_fps.frames_per_second() as f32,
+            // There's no audio yet, so nothing is ever playing.
+            Stat::Sfx(_) | Stat::SfxNote(_) | Stat::MusicPattern => -1.0,
+            Stat::MusicPatternCount | Stat::MusicTicks | Stat::MusicPlaying => 0.0,
+            Stat::KeyPressed => bool_to_f32(self.state.typed_char().is_some()),
+            Stat::KeyChar => self.state.typed_char().map_or(0.0, |c| c as u32 as f32),
+            Stat::MouseX => self.state.mouse_x as f32,
+            Stat::MouseY => self.state.mouse_y as f32,
+            Stat::MouseButtons => self.state.mouse_buttons() as f32,
+            Stat::MouseWheel => self.state.mouse_wheel().1 as f32,
+            Stat::UtcTime(field) => field.of(&chrono::Utc::now()),
+            Stat::LocalTime(field) => field.of(&chrono::Local::now()),
+        }
+    }
+
     // Non-standard stuf
     pub fn append_camera(&mut self, x: i32, y: i32) {
         self.draw_data.append_camera(x, y);
@@ -179,9 +315,54 @@ impl Pico8 {
         self.state.mouse()
     }
 
+    /// How much the mouse wheel scrolled (horizontally, vertically) in the last frame.
+    pub fn mouse_wheel(&self) -> (i32, i32) {
+        self.state.mouse_wheel()
+    }
+
     pub fn set_title(&mut self, new_title: String) {
         self.new_title = Some(new_title);
     }
+
+    /// Lets [`Pico8::stat`] and [`Pico8::time`] know how the game is running.
+    /// Game loops should call this every tick, with how long the tick was (`delta_millis`),
+    /// how many `updates` it ran (at `fps`), and how long running them and drawing took (`busy_millis`).
+    ///
+    /// Also persists the values set with [`Pico8::dset`] during the tick.
+    pub fn record_tick(&mut self, fps: Fps, delta_millis: f64, updates: u32, busy_millis: f64) {
+        self.frame_stats
+            .on_tick(fps, delta_millis, updates, busy_millis);
+        self.cartdata.flush();
+    }
+
+    /// Opens the pause menu (if it's enabled), or closes it if it was open.
+    pub fn toggle_pause_menu(&mut self) {
+        if self.pause_menu.is_open() {
+            self.pause_menu.close(&mut self.draw_data);
+        } else {
+            self.pause_menu.open(&self.draw_data);
+        }
+    }
+
+    /// Whether the pause menu is open, in which case the game shouldn't be updated or drawn.
+    pub fn is_paused(&self) -> bool {
+        self.pause_menu.is_open()
+    }
+
+    /// Handles the pause menu's input, returning what the player chose (which also closes the menu).
+    pub fn update_pause_menu(&mut self) -> Option<MenuAction> {
+        self.pause_menu.update(&self.state, &mut self.draw_data)
+    }
+
+    /// Draws the pause menu over the game's last frame.
+    pub fn draw_pause_menu(&mut self) {
+        self.pause_menu.draw(&mut self.draw_data);
+    }
+
+    /// Restarts [`Pico8::time`] and [`Pico8::frame_count`] from 0, e.g. when restarting the game.
+    pub fn reset_time(&mut self) {
+        self.frame_stats = FrameStats::new();
+    }
 }
 
 // Utility pub(crate) methods
@@ -208,6 +389,14 @@ impl Pico8 {
 // Top level functions that pico8 provides that don't modify the global state.
 // cos, sin, etc.
 
+thread_local! {
+    // Each thread gets its own generator, so that games (and tests) running
+    // in parallel don't affect each other's random numbers.
+    // Unlike `StdRng`, ChaCha8's algorithm won't change in a later version of rand,
+    // so a seed (like the one in a `Recording`) always generates the same numbers.
+    static RNG: RefCell<ChaCha8Rng> = RefCell::new(ChaCha8Rng::from_entropy());
+}
+
 /// Pico8's [`sin`](<https://pico-8.fandom.com/wiki/Sin>) function.
 pub fn sin(f: f32) -> f32 {
     (-f * 2.0 * PI).sin()
@@ -215,7 +404,15 @@ pub fn sin(f: f32) -> f32 {
 
 /// Pico8's [`rnd`](<https://pico-8.fandom.com/wiki/Rnd>) function.
 pub fn rnd(limit: f32) -> f32 {
-    rand::thread_rng().gen_range(0.0..limit)
+    RNG.with(|rng| rng.borrow_mut().gen_range(0.0..limit))
+}
+
+/// Pico8's [`srand`](<https://pico-8.fandom.com/wiki/Srand>) function.
+///
+/// Seeds the random number generator used by [`rnd`] (on the current thread),
+/// so that the same sequence of numbers is generated every time.
+pub fn srand(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = ChaCha8Rng::seed_from_u64(seed));
 }
 
 /// Pico8's [`mid`](<https://pico-8.fandom.com/wiki/Mid>) function.
@@ -228,7 +425,8 @@ pub fn mid(first: f32, second: f32, third: f32) -> f32 {
 
 #[cfg(test)]
 mod tests {
-    use super::{mid, rnd, sin};
+    use super::{mid, rnd, sin, srand, Pico8};
+    use crate::Resources;
 
     macro_rules! assert_delta {
         ($x:expr, $y:expr, $d:expr) => {
@@ -260,6 +458,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn srand_makes_rnd_deterministic() {
+        srand(42);
+        let first: Vec<f32> = (0..10).map(|_| rnd(1.0)).collect();
+        srand(42);
+        let second: Vec<f32> = (0..10).map(|_| rnd(1.0)).collect();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn seeds_always_generate_the_same_numbers() {
+        srand(42);
+        let numbers: Vec<f32> = (0..3).map(|_| rnd(100.0)).collect();
+
+        // Saved recordings stop reproducing if these ever change.
+        assert_eq!(numbers, [22.408068, 68.189606, 14.638615]);
+    }
+
+    #[test]
+    fn unsupported_stats_are_0() {
+        let pico8 = Pico8::new(Resources::empty(""));
+
+        assert_eq!(pico8.stat(0), 0.0);
+        assert_eq!(pico8.stat(4), 0.0);
+        assert_eq!(pico8.stat(6), 0.0);
+        assert_eq!(pico8.stat(-1), 0.0);
+    }
+
+    #[test]
+    fn pico8_can_be_sent_between_threads() {
+        fn assert_send<T: Send>() {}
+
+        assert_send::<Pico8>();
+    }
+
+    #[test]
+    fn restoring_a_snapshot_restores_the_state() {
+        let mut pico8 = Pico8::new(Resources::empty(""));
+        srand(3);
+        pico8.pset(1, 1, 7);
+        pico8.mset(2, 2, 5);
+
+        let snapshot = pico8.snapshot();
+        let random_numbers: Vec<f32> = (0..3).map(|_| rnd(1.0)).collect();
+        let buffer = pico8.draw_data.buffer().to_vec();
+        pico8.cls(0);
+        pico8.mset(2, 2, 0);
+
+        pico8.restore(&snapshot);
+
+        assert_eq!(pico8.mget(2, 2), 5);
+        assert_eq!(pico8.draw_data.buffer(), buffer.as_slice());
+        assert_eq!(
+            (0..3).map(|_| rnd(1.0)).collect::<Vec<f32>>(),
+            random_numbers
+        );
+    }
+
     #[test]
     fn mid_works() {
         assert_delta!(mid(8.0, 2.0, 4.0), 4.0, 0.00001);
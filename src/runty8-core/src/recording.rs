@@ -0,0 +1,245 @@
+use crate::serialize::Serialize;
+use crate::Input;
+use itertools::Itertools;
+
+/// The input of every frame of a game session, along with the seed `rnd` was started with.
+///
+/// Feeding it back to the game frame by frame reproduces the session exactly,
+/// which is useful to reproduce bugs found while playtesting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recording {
+    seed: u64,
+    frames: Vec<Frame>,
+}
+
+/// What a game can see of [`Input`] during a single frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Frame {
+    // Bitmask of the held buttons, in the order of `Frame::buttons`.
+    buttons: u16,
+    mouse: (i32, i32),
+    wheel: (i32, i32),
+    // The devkit keyboard: how many characters were typed so far, and the last one.
+    typed_chars: u32,
+    last_typed_char: Option<char>,
+}
+
+impl Frame {
+    fn new(input: &Input) -> Self {
+        // `None` (no event received yet) behaves like a released button:
+        // buttons can only be "just pressed" after a `Some(true)`.
+        let buttons = Self::buttons(input)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, button)| *button == Some(true))
+            .fold(0, |mask, (bit, _)| mask | (1 << bit));
+
+        Self {
+            buttons,
+            mouse: (input.mouse_x, input.mouse_y),
+            wheel: (input.wheel_x, input.wheel_y),
+            typed_chars: input.typed_chars,
+            last_typed_char: input.last_typed_char,
+        }
+    }
+
+    fn buttons(input: &Input) -> [Option<bool>; 10] {
+        [
+            input.left,
+            input.right,
+            input.up,
+            input.down,
+            input.x,
+            input.c,
+            input.mouse,
+            input.mouse_right,
+            input.mouse_middle,
+            input.pause,
+        ]
+    }
+
+    fn to_input(self) -> Input {
+        let held = |bit: u16| Some(self.buttons & (1 << bit) != 0);
+
+        Input {
+            left: held(0),
+            right: held(1),
+            up: held(2),
+            down: held(3),
+            x: held(4),
+            c: held(5),
+            mouse: held(6),
+            mouse_right: held(7),
+            mouse_middle: held(8),
+            mouse_x: self.mouse.0,
+            mouse_y: self.mouse.1,
+            wheel_x: self.wheel.0,
+            wheel_y: self.wheel.1,
+            typed_chars: self.typed_chars,
+            last_typed_char: self.last_typed_char,
+            pause: held(9),
+        }
+    }
+}
+
+impl Recording {
+    /// Starts an empty recording, for a session where `rnd` was seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            frames: vec![],
+        }
+    }
+
+    /// The seed `rnd` has to be seeded with (see [`srand`](crate::srand)) before replaying.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Adds a frame, with the input the game saw in it.
+    pub fn record(&mut self, input: &Input) {
+        self.frames.push(Frame::new(input));
+    }
+
+    /// Amount of recorded frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The input to feed to `State::update_input` in the given frame.
+    pub fn frame(&self, index: usize) -> Option<Input> {
+        self.frames.get(index).map(|frame| frame.to_input())
+    }
+
+    pub fn deserialize(str: &str) -> Result<Self, String> {
+        let mut lines = str.lines();
+
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .ok_or_else(|| "[Recording] Missing seed".to_owned())?
+            .parse()
+            .map_err(|_| "[Recording] Invalid seed".to_owned())?;
+
+        let mut frames = vec![];
+        for (line_number, line) in lines.enumerate() {
+            let invalid_line = || format!("[Recording] Invalid frame on line {}", line_number + 2);
+
+            let mut numbers: Vec<i64> = line
+                .split_whitespace()
+                .map(|number| number.parse().map_err(|_| invalid_line()))
+                .collect::<Result<_, _>>()?;
+            // Recordings without the keyboard columns are from before it was recorded.
+            if numbers.len() == 6 {
+                numbers.extend([0, 0]);
+            }
+            let numbers: [i64; 8] = numbers.try_into().map_err(|_| invalid_line())?;
+            let [count, buttons, mouse_x, mouse_y, wheel_x, wheel_y, typed_chars, last_typed_char] =
+                numbers;
+            let as_i32 = |number: i64| i32::try_from(number).map_err(|_| invalid_line());
+            let as_u32 = |number: i64| u32::try_from(number).map_err(|_| invalid_line());
+            // 0 when nothing was typed yet.
+            let last_typed_char = match as_u32(last_typed_char)? {
+                0 => None,
+                code => Some(char::from_u32(code).ok_or_else(invalid_line)?),
+            };
+
+            let frame = Frame {
+                buttons: u16::try_from(buttons).map_err(|_| invalid_line())?,
+                mouse: (as_i32(mouse_x)?, as_i32(mouse_y)?),
+                wheel: (as_i32(wheel_x)?, as_i32(wheel_y)?),
+                typed_chars: as_u32(typed_chars)?,
+                last_typed_char,
+            };
+            let count = usize::try_from(count).map_err(|_| invalid_line())?;
+            frames.resize(frames.len() + count, frame);
+        }
+
+        Ok(Self { seed, frames })
+    }
+}
+
+/// One line for the seed, and then one line per run of identical frames:
+/// `<frame count> <buttons> <mouse x> <mouse y> <wheel x> <wheel y> <typed chars> <last typed char>`,
+/// with the last typed character as its code (0 if there's none).
+impl Serialize for Recording {
+    fn serialize(&self) -> String {
+        let runs = self.frames.iter().dedup_with_count().map(|(count, frame)| {
+            format!(
+                "{count} {} {} {} {} {} {} {}",
+                frame.buttons,
+                frame.mouse.0,
+                frame.mouse.1,
+                frame.wheel.0,
+                frame.wheel.1,
+                frame.typed_chars,
+                frame.last_typed_char.map_or(0, u32::from)
+            )
+        });
+
+        std::iter::once(format!("seed {}", self.seed))
+            .chain(runs)
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InputEvent, Key, KeyState, KeyboardEvent, MouseEvent};
+
+    fn press(input: &mut Input, key: Key) {
+        input.on_event(InputEvent::Keyboard(KeyboardEvent {
+            key,
+            state: KeyState::Down,
+        }));
+    }
+
+    #[test]
+    fn recording_roundtrip_works() {
+        let mut input = Input::new();
+        let mut recording = Recording::new(1234);
+
+        for _ in 0..20 {
+            recording.record(&input);
+        }
+        press(&mut input, Key::X);
+        press(&mut input, Key::LeftArrow);
+        press(&mut input, Key::A);
+        input.on_event(InputEvent::Mouse(MouseEvent::Move { x: 3, y: -4 }));
+        input.on_event(InputEvent::Mouse(MouseEvent::Wheel {
+            delta_x: 0,
+            delta_y: 2,
+        }));
+        recording.record(&input);
+
+        let serialized = recording.serialize();
+        assert_eq!(
+            serialized,
+            "seed 1234\n20 0 64 64 0 0 0 0\n1 17 3 -4 0 2 2 97"
+        );
+        assert_eq!(Recording::deserialize(&serialized), Ok(recording.clone()));
+
+        let last_frame = recording.frame(20).unwrap();
+        assert_eq!(last_frame.x, Some(true));
+        assert_eq!(last_frame.left, Some(true));
+        assert_eq!(last_frame.c, Some(false));
+        assert_eq!((last_frame.mouse_x, last_frame.mouse_y), (3, -4));
+        assert_eq!(last_frame.typed_chars, 2);
+        assert_eq!(last_frame.last_typed_char, Some('a'));
+        assert!(recording.frame(21).is_none());
+    }
+
+    #[test]
+    fn deserialize_rejects_invalid_frames() {
+        assert!(Recording::deserialize("").is_err());
+        assert!(Recording::deserialize("seed 1\n1 0 0 0").is_err());
+        assert!(Recording::deserialize("seed 1\n1 70000 0 0 0 0").is_err());
+        assert!(Recording::deserialize("seed 1\n1 0 0 0 0 0 1 55296").is_err());
+        assert!(Recording::deserialize("seed 1\n1 0 0 0 0 0").is_ok());
+    }
+}
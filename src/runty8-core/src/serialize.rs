@@ -5,16 +5,29 @@ use crate::map::Map;
 use crate::sprite_sheet::SpriteSheet;
 use std::fmt::Display;
 
-fn write_and_log(file_name: &str, contents: &str) {
+fn write_and_log(file_name: &str, contents: &str) -> std::io::Result<()> {
     print!("Writing {file_name}... ");
-    std::fs::write(file_name, contents).unwrap();
-    println!("success.")
+    let result = std::fs::write(file_name, contents);
+    println!(
+        "{}",
+        if result.is_ok() {
+            "success."
+        } else {
+            "failed."
+        }
+    );
+
+    result
 }
 
-pub fn serialize(assets_path: &str, file_name: &str, serializable: &impl Serialize) {
+pub fn serialize(
+    assets_path: &str,
+    file_name: &str,
+    serializable: &impl Serialize,
+) -> std::io::Result<()> {
     let file_path = format!("{assets_path}/{file_name}");
 
-    write_and_log(&file_path, &serializable.serialize());
+    write_and_log(&file_path, &serializable.serialize())
 }
 
 pub trait Serialize {
@@ -28,7 +41,7 @@ impl<T: Serialize + ?Sized> Serialize for &T {
 }
 
 #[repr(C, packed)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct Color {
     r: u8,
     g: u8,
@@ -58,6 +71,7 @@ const ROWS_PER_PAGE: usize = 4;
 /// Utility to create PPM images.
 /// Useful for debugging our data structures (sprite sheet, map)
 /// in regular image viewers.
+#[derive(Debug, PartialEq, Eq)]
 pub struct Ppm {
     height: usize,
     width: usize,
@@ -65,6 +79,89 @@ pub struct Ppm {
 }
 
 impl Ppm {
+    /// Creates an image from RGB pixels, like the ones in the screen buffer.
+    pub fn from_rgb(width: usize, height: usize, pixels: &[u8]) -> Self {
+        assert_eq!(pixels.len(), 3 * width * height);
+
+        let data = pixels
+            .chunks_exact(3)
+            .map(|rgb| Color {
+                r: rgb[0],
+                g: rgb[1],
+                b: rgb[2],
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// The image's pixels, in RGB format.
+    pub fn to_rgb(&self) -> Vec<u8> {
+        self.data
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Parses a plain PPM (P3) image, like the ones produced by [`Serialize::serialize`].
+    pub fn deserialize(str: &str) -> Result<Self, String> {
+        let mut tokens = str
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(|line| line.split_ascii_whitespace());
+
+        if tokens.next() != Some("P3") {
+            return Err("[Ppm] Only plain PPM (P3) images are supported".to_owned());
+        }
+
+        let mut numbers = tokens.map(|token| {
+            token
+                .parse::<usize>()
+                .map_err(|err| format!("[Ppm] Invalid number {token:?}: {err}"))
+        });
+        let mut next_number = || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err("[Ppm] Unexpected end of file".to_owned()))
+        };
+
+        let width = next_number()?;
+        let height = next_number()?;
+        let max_value = next_number()?;
+        if max_value != 255 {
+            return Err(format!(
+                "[Ppm] Only 8 bit images are supported, got max value {max_value}"
+            ));
+        }
+
+        let components = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| format!("[Ppm] Image too big: {width}x{height}"))?;
+        let pixels = (0..components)
+            .map(|_| {
+                let component = next_number()?;
+
+                u8::try_from(component)
+                    .map_err(|_| format!("[Ppm] Color component {component} is over 255"))
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        Ok(Self::from_rgb(width, height, &pixels))
+    }
+
     #[allow(dead_code)]
     pub fn from_map(map: &Map, sprite_sheet: &SpriteSheet) -> Self {
         let width = 1024;
@@ -149,3 +246,42 @@ impl Serialize for Ppm {
         format!("{header}\n{body}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ppm_roundtrip_works() {
+        let pixels = [
+            255, 0, 77, 0, 0, 0, 1, 2, 3, 41, 173, 255, 7, 7, 7, 200, 100, 50,
+        ];
+        let ppm = Ppm::from_rgb(3, 2, &pixels);
+
+        let deserialized = Ppm::deserialize(&ppm.serialize()).unwrap();
+
+        assert_eq!(deserialized, ppm);
+        assert_eq!(deserialized.to_rgb(), pixels);
+    }
+
+    #[test]
+    fn ppm_deserialize_handles_comments() {
+        let ppm = Ppm::deserialize("P3\n# A comment\n1 1\n255\n1 2 3 # Another one\n").unwrap();
+
+        assert_eq!((ppm.width(), ppm.height()), (1, 1));
+        assert_eq!(ppm.to_rgb(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn ppm_deserialize_rejects_truncated_images() {
+        assert!(Ppm::deserialize("P3\n2 2\n255\n1 2 3").is_err());
+        assert!(Ppm::deserialize("P6\n1 1\n255\n1 2 3").is_err());
+    }
+
+    #[test]
+    fn ppm_deserialize_rejects_invalid_headers_and_components() {
+        let huge = usize::MAX / 2;
+        assert!(Ppm::deserialize(&format!("P3\n{huge} {huge}\n255\n1 2 3")).is_err());
+        assert!(Ppm::deserialize("P3\n1 1\n255\n1 256 3").is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use itertools::Itertools;
 
+#[cfg(feature = "png-import")]
+use crate::image_import::{Image, ImportOptions};
 use crate::serialize::Serialize;
 use crate::Color;
 
@@ -49,6 +51,11 @@ impl SpriteSheet {
         self.sprite_sheet[Self::to_linear_index(x, y)] = c;
     }
 
+    /// The color of the pixel at coordinate (x,y) in the spritesheet
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.sprite_sheet[Self::to_linear_index(x, y)]
+    }
+
     pub fn to_linear_index(x: usize, y: usize) -> usize {
         let x_part = 64 * (x / 8) + x % 8;
         let y_part = 16 * 64 * (y / 8) + 8 * (y % 8);
@@ -68,6 +75,121 @@ impl SpriteSheet {
         Sprite::new_mut(&mut self.sprite_sheet[index..(index + Sprite::WIDTH * Sprite::HEIGHT)])
     }
 
+    /// Rotates the `size` x `size` square of pixels with its top left corner in (x, y)
+    /// by 90°, clockwise.
+    pub fn rotate_clockwise(&mut self, x: usize, y: usize, size: usize) {
+        let pixels = self.region(x, y, size, size);
+
+        self.set_region(x, y, size, &rotate_clockwise(&pixels, size));
+    }
+
+    /// Scales the `width` x `height` pixels with their top left corner in (x, y)
+    /// to `new_width` x `new_height`, taking the color of the nearest pixel (without smoothing).
+    /// The top left corner stays in place, pixels that don't fit in the sprite sheet are dropped,
+    /// and the ones of the original rectangle left uncovered become transparent (0).
+    pub fn scale(
+        &mut self,
+        x: usize,
+        y: usize,
+        (width, height): (usize, usize),
+        (new_width, new_height): (usize, usize),
+    ) {
+        let pixels = self.region(x, y, width, height);
+        let scaled = (0..new_height)
+            .flat_map(|new_y| {
+                (0..new_width)
+                    .map(move |new_x| (new_x * width / new_width, new_y * height / new_height))
+            })
+            .map(|(old_x, old_y)| pixels[old_x + old_y * width])
+            .collect::<Vec<_>>();
+
+        self.set_region(x, y, width, &vec![0; width * height]);
+        self.set_region(x, y, new_width, &scaled);
+    }
+
+    /// Draws an outline of `color` around the non transparent (non 0) pixels
+    /// in the `width` x `height` rectangle with its top left corner in (x, y).
+    pub fn outline(&mut self, x: usize, y: usize, (width, height): (usize, usize), color: Color) {
+        let pixels = self.region(x, y, width, height);
+
+        self.set_region(x, y, width, &outline(&pixels, width, color));
+    }
+
+    /// Replaces the pixels of color `from` with `to`,
+    /// in the `width` x `height` rectangle with its top left corner in (x, y).
+    pub fn replace_color(
+        &mut self,
+        x: usize,
+        y: usize,
+        (width, height): (usize, usize),
+        from: Color,
+        to: Color,
+    ) {
+        let mut pixels = self.region(x, y, width, height);
+        replace_color(&mut pixels, from, to);
+
+        self.set_region(x, y, width, &pixels);
+    }
+
+    /// Imports a PNG image into the `width` x `height` rectangle with its top left corner
+    /// in (x, y), converting each pixel to the nearest pico8 color.
+    /// Bigger images are cropped to the rectangle (keeping their top left corner),
+    /// and the pixels of the rectangle not covered by smaller ones are left as they were.
+    #[cfg(feature = "png-import")]
+    pub fn import_png(
+        &mut self,
+        png: &[u8],
+        x: usize,
+        y: usize,
+        (width, height): (usize, usize),
+        options: ImportOptions,
+    ) -> Result<(), String> {
+        let image = Image::from_png(png)?;
+        let colors = image.to_pico8(options);
+        let visible = colors
+            .chunks(image.width)
+            .take(height)
+            .flat_map(|row| row.iter().take(width))
+            .copied()
+            .collect::<Vec<_>>();
+
+        self.set_region(x, y, image.width.min(width), &visible);
+
+        Ok(())
+    }
+
+    /// The colors of the `width` x `height` pixels with their top left corner in (x, y), row by row.
+    /// Pixels outside of the sprite sheet are transparent.
+    fn region(&self, x: usize, y: usize, width: usize, height: usize) -> Vec<Color> {
+        (y..y + height)
+            .flat_map(|y| (x..x + width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                if Self::contains(x, y) {
+                    self.get(x, y)
+                } else {
+                    0
+                }
+            })
+            .collect()
+    }
+
+    /// Sets the pixels of a region `width` pixels wide, from its colors (row by row).
+    /// Pixels outside of the sprite sheet are skipped.
+    fn set_region(&mut self, x: usize, y: usize, width: usize, colors: &[Color]) {
+        for (index, &color) in colors.iter().enumerate() {
+            let (x, y) = (x + index % width, y + index / width);
+
+            if Self::contains(x, y) {
+                self.set(x, y, color);
+            }
+        }
+    }
+
+    fn contains(x: usize, y: usize) -> bool {
+        x < Self::SPRITES_PER_ROW * Sprite::WIDTH
+            && y < Self::SPRITE_COUNT / Self::SPRITES_PER_ROW * Sprite::HEIGHT
+    }
+
     fn sprite_index(&self, sprite: usize) -> usize {
         // How many pixels we need to skip to get to the start of this sprite.
         sprite * Sprite::WIDTH * Sprite::HEIGHT
@@ -185,6 +307,25 @@ impl Sprite {
         }
     }
 
+    /// Rotates the sprite by 90°, clockwise.
+    pub fn rotate_clockwise(&mut self) {
+        let rotated = rotate_clockwise(&self.sprite, Self::WIDTH);
+
+        self.sprite.copy_from_slice(&rotated);
+    }
+
+    /// Draws an outline of `color` around the non transparent (non 0) pixels.
+    pub fn outline(&mut self, color: Color) {
+        let outlined = outline(&self.sprite, Self::WIDTH, color);
+
+        self.sprite.copy_from_slice(&outlined);
+    }
+
+    /// Replaces the pixels of color `from` with `to`.
+    pub fn replace_color(&mut self, from: Color, to: Color) {
+        replace_color(&mut self.sprite, from, to);
+    }
+
     pub fn flip_vertically(&mut self) {
         for x in 0..(Self::WIDTH as isize) {
             for y in 0..((Self::HEIGHT / 2) as isize) {
@@ -197,6 +338,48 @@ impl Sprite {
     }
 }
 
+// The operations below work on the pixels of a rectangle, row by row.
+
+fn rotate_clockwise(pixels: &[Color], size: usize) -> Vec<Color> {
+    (0..size * size)
+        .map(|index| {
+            let (x, y) = (index % size, index / size);
+
+            pixels[y + (size - 1 - x) * size]
+        })
+        .collect()
+}
+
+/// Transparent pixels next to (but not diagonal to) an opaque one take `color`.
+fn outline(pixels: &[Color], width: usize, color: Color) -> Vec<Color> {
+    let height = pixels.len() / width;
+    let is_opaque = |x: usize, y: usize| x < width && y < height && pixels[x + y * width] != 0;
+
+    pixels
+        .iter()
+        .enumerate()
+        .map(|(index, &pixel)| {
+            let (x, y) = (index % width, index / width);
+            let next_to_opaque = is_opaque(x + 1, y)
+                || is_opaque(x, y + 1)
+                || (x > 0 && is_opaque(x - 1, y))
+                || (y > 0 && is_opaque(x, y - 1));
+
+            if pixel == 0 && next_to_opaque {
+                color
+            } else {
+                pixel
+            }
+        })
+        .collect()
+}
+
+fn replace_color(pixels: &mut [Color], from: Color, to: Color) {
+    for pixel in pixels.iter_mut().filter(|pixel| **pixel == from) {
+        *pixel = to;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +392,154 @@ mod tests {
         assert_eq!(SpriteSheet::to_linear_index(8, 1), 64 + 8);
         assert_eq!(SpriteSheet::to_linear_index(1, 9), 1033);
     }
+
+    /// A sprite from its rows, as strings of hex digits.
+    fn sprite(rows: [&str; 8]) -> Vec<Color> {
+        rows.iter()
+            .flat_map(|row| row.chars().map(|c| c.to_digit(16).unwrap() as Color))
+            .collect()
+    }
+
+    #[test]
+    fn sprites_can_be_rotated() {
+        let mut pixels = sprite([
+            "12000000", "30000000", "00000000", "00000000", "00000000", "00000000", "00000000",
+            "00000004",
+        ]);
+        let sprite = Sprite::new_mut(&mut pixels);
+
+        sprite.rotate_clockwise();
+
+        assert_eq!(
+            sprite.to_owned(),
+            self::sprite([
+                "00000031", "00000002", "00000000", "00000000", "00000000", "00000000", "00000000",
+                "40000000",
+            ])
+        );
+    }
+
+    #[test]
+    fn sprites_can_be_outlined() {
+        let mut pixels = sprite([
+            "00000000", "00000000", "00077000", "00070000", "00000000", "00000000", "00000000",
+            "70000000",
+        ]);
+        let sprite = Sprite::new_mut(&mut pixels);
+
+        sprite.outline(1);
+
+        assert_eq!(
+            sprite.to_owned(),
+            self::sprite([
+                "00000000", "00011000", "00177100", "00171000", "00010000", "00000000", "10000000",
+                "71000000",
+            ])
+        );
+    }
+
+    #[test]
+    fn colors_can_be_replaced() {
+        let mut pixels = sprite([
+            "12121212", "00000000", "00000000", "00000000", "00000000", "00000000", "00000000",
+            "00000000",
+        ]);
+        let sprite = Sprite::new_mut(&mut pixels);
+
+        sprite.replace_color(2, 9);
+
+        assert_eq!(sprite.pget(0, 0), 1);
+        assert_eq!(sprite.pget(1, 0), 9);
+        assert_eq!(sprite.iter().filter(|&color| color == 9).count(), 4);
+    }
+
+    #[test]
+    fn regions_of_the_sprite_sheet_can_be_transformed() {
+        let mut sprite_sheet = SpriteSheet::new();
+        // A 2x2 square across the first 2 sprites.
+        sprite_sheet.set(7, 0, 1);
+        sprite_sheet.set(8, 0, 2);
+        sprite_sheet.set(7, 1, 3);
+        sprite_sheet.set(8, 1, 4);
+
+        sprite_sheet.rotate_clockwise(7, 0, 2);
+        assert_eq!(sprite_sheet.region(7, 0, 2, 2), [3, 1, 4, 2]);
+
+        sprite_sheet.replace_color(7, 0, (2, 1), 1, 5);
+        assert_eq!(sprite_sheet.region(7, 0, 2, 2), [3, 5, 4, 2]);
+
+        sprite_sheet.outline(6, 0, (1, 2), 9);
+        assert_eq!(sprite_sheet.region(6, 0, 2, 2), [0, 3, 0, 4]);
+        sprite_sheet.outline(6, 0, (2, 2), 9);
+        assert_eq!(sprite_sheet.region(6, 0, 2, 2), [9, 3, 9, 4]);
+    }
+
+    #[test]
+    fn regions_are_scaled_using_the_nearest_pixel() {
+        let mut sprite_sheet = SpriteSheet::new();
+        sprite_sheet.set(0, 0, 1);
+        sprite_sheet.set(1, 0, 2);
+
+        sprite_sheet.scale(0, 0, (2, 1), (4, 2));
+        assert_eq!(sprite_sheet.region(0, 0, 4, 2), [1, 1, 2, 2, 1, 1, 2, 2]);
+
+        sprite_sheet.scale(0, 0, (4, 2), (2, 1));
+        assert_eq!(sprite_sheet.region(0, 0, 4, 2), [1, 2, 0, 0, 0, 0, 0, 0]);
+
+        // Pixels that don't fit are dropped.
+        sprite_sheet.scale(126, 126, (2, 2), (4, 4));
+    }
+
+    /// A PNG image from its RGBA pixels, row by row.
+    #[cfg(feature = "png-import")]
+    fn png(width: u32, height: u32, pixels: &[[u8; 4]]) -> Vec<u8> {
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&pixels.concat())
+            .unwrap();
+
+        png
+    }
+
+    #[test]
+    #[cfg(feature = "png-import")]
+    fn png_images_are_imported_into_a_region() {
+        let mut sprite_sheet = SpriteSheet::new();
+        sprite_sheet.set(9, 1, 5);
+        // Red, slightly off white, transparent, and another row that doesn't fit.
+        let image = png(
+            3,
+            2,
+            &[
+                [0xFF, 0x00, 0x4D, 0xFF],
+                [0xF0, 0xF0, 0xF0, 0xFF],
+                [0x29, 0xAD, 0xFF, 0x00],
+                [0x29, 0xAD, 0xFF, 0xFF],
+                [0x29, 0xAD, 0xFF, 0xFF],
+                [0x29, 0xAD, 0xFF, 0xFF],
+            ],
+        );
+        let options = ImportOptions {
+            dither: false,
+            alpha_as_transparent: true,
+        };
+
+        sprite_sheet
+            .import_png(&image, 7, 0, (4, 1), options)
+            .unwrap();
+        assert_eq!(sprite_sheet.region(7, 0, 4, 2), [8, 7, 0, 0, 0, 0, 5, 0]);
+
+        sprite_sheet
+            .import_png(&image, 7, 0, (4, 1), ImportOptions::default())
+            .unwrap();
+        assert_eq!(sprite_sheet.region(7, 0, 3, 1), [8, 7, 12]);
+
+        assert!(sprite_sheet
+            .import_png(b"not a png", 0, 0, (8, 8), options)
+            .is_err());
+    }
 }
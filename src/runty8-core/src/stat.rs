@@ -0,0 +1,207 @@
+use crate::Fps;
+
+/// Typed version of the queries supported by [`Pico8::stat`](crate::Pico8::stat).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stat {
+    /// `stat(1)`: fraction of the last frame spent updating and drawing.
+    /// Goes over 1 when the game can't keep up.
+    CpuUsage,
+    /// `stat(2)`: same as [`Stat::CpuUsage`], runty8 doesn't have system overhead to add.
+    SystemCpuUsage,
+    /// `stat(7)`: frames drawn per second, measured over the last second.
+    Fps,
+    /// `stat(8)`: frames per second the game is meant to run at, see [`App::FPS`](crate::App::FPS).
+    TargetFps,
+    /// `stat(9)`: same as [`Stat::Fps`].
+    HostFps,
+    /// `stat(16..=19)` and `stat(46..=49)`: sfx playing on a channel (0 to 3), -1 if none.
+    Sfx(u8),
+    /// `stat(20..=23)` and `stat(50..=53)`: note being played by the sfx on a channel, -1 if none.
+    SfxNote(u8),
+    /// `stat(24)` and `stat(54)`: current music pattern, -1 if no music is playing.
+    MusicPattern,
+    /// `stat(25)` and `stat(55)`: amount of music patterns played.
+    MusicPatternCount,
+    /// `stat(26)` and `stat(56)`: ticks played in the current music pattern.
+    MusicTicks,
+    /// `stat(57)`: 1 if music is playing, 0 otherwise.
+    MusicPlaying,
+    /// `stat(30)`: 1 if a key was typed in the last frame (devkit keyboard), 0 otherwise.
+    KeyPressed,
+    /// `stat(31)`: code of the character typed in the last frame, 0 if none.
+    ///
+    /// Pico8 returns the character itself, use `char::from(stat as u8)` to get it.
+    KeyChar,
+    /// `stat(32)`: mouse x.
+    MouseX,
+    /// `stat(33)`: mouse y.
+    MouseY,
+    /// `stat(34)`: bitmask of held mouse buttons (1 = left, 2 = right, 4 = middle).
+    MouseButtons,
+    /// `stat(36)`: mouse wheel movement in the last frame (positive when scrolling up).
+    MouseWheel,
+    /// `stat(80..=85)`: current UTC date and time.
+    UtcTime(TimeField),
+    /// `stat(90..=95)`: current local date and time.
+    LocalTime(TimeField),
+}
+
+/// A component of a date and time, see [`Stat::UtcTime`] and [`Stat::LocalTime`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeField {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl Stat {
+    /// The query behind `stat(n)`, if it's supported.
+    pub fn from_index(n: i32) -> Option<Self> {
+        let channel = |first| (n - first) as u8;
+
+        let stat = match n {
+            1 => Stat::CpuUsage,
+            2 => Stat::SystemCpuUsage,
+            7 => Stat::Fps,
+            8 => Stat::TargetFps,
+            9 => Stat::HostFps,
+            16..=19 => Stat::Sfx(channel(16)),
+            20..=23 => Stat::SfxNote(channel(20)),
+            24 | 54 => Stat::MusicPattern,
+            25 | 55 => Stat::MusicPatternCount,
+            26 | 56 => Stat::MusicTicks,
+            30 => Stat::KeyPressed,
+            31 => Stat::KeyChar,
+            32 => Stat::MouseX,
+            33 => Stat::MouseY,
+            34 => Stat::MouseButtons,
+            36 => Stat::MouseWheel,
+            46..=49 => Stat::Sfx(channel(46)),
+            50..=53 => Stat::SfxNote(channel(50)),
+            57 => Stat::MusicPlaying,
+            80..=85 => Stat::UtcTime(TimeField::from_offset(n - 80)),
+            90..=95 => Stat::LocalTime(TimeField::from_offset(n - 90)),
+            _ => return None,
+        };
+
+        Some(stat)
+    }
+}
+
+impl TimeField {
+    fn from_offset(offset: i32) -> Self {
+        match offset {
+            0 => TimeField::Year,
+            1 => TimeField::Month,
+            2 => TimeField::Day,
+            3 => TimeField::Hour,
+            4 => TimeField::Minute,
+            _ => TimeField::Second,
+        }
+    }
+
+    pub(crate) fn of<T: chrono::Datelike + chrono::Timelike>(self, date_time: &T) -> f32 {
+        let value = match self {
+            TimeField::Year => date_time.year() as u32,
+            TimeField::Month => date_time.month(),
+            TimeField::Day => date_time.day(),
+            TimeField::Hour => date_time.hour(),
+            TimeField::Minute => date_time.minute(),
+            TimeField::Second => date_time.second(),
+        };
+
+        value as f32
+    }
+}
+
+/// How fast the game is running, and for how long it's been running.
+/// See [`Pico8::record_tick`](crate::Pico8::record_tick).
+#[derive(Clone, Debug)]
+pub(crate) struct FrameStats {
+    pub(crate) target_fps: Fps,
+    pub(crate) cpu_usage: f32,
+    // Updates run so far, and how much (fixed timestep) time they add up to.
+    pub(crate) frames: u32,
+    pub(crate) elapsed_millis: f64,
+    // Until a full second has been measured.
+    fps: Option<f32>,
+    window_millis: f64,
+    window_frames: u32,
+}
+
+impl FrameStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            target_fps: Fps::Thirty,
+            cpu_usage: 0.0,
+            frames: 0,
+            elapsed_millis: 0.0,
+            fps: None,
+            window_millis: 0.0,
+            window_frames: 0,
+        }
+    }
+
+    pub(crate) fn on_tick(&mut self, fps: Fps, delta_millis: f64, updates: u32, busy_millis: f64) {
+        self.target_fps = fps;
+        self.frames += updates;
+        self.elapsed_millis += updates as f64 * fps.delta_millis();
+
+        if updates > 0 {
+            self.cpu_usage = (busy_millis / (updates as f64 * fps.delta_millis())) as f32;
+            self.window_frames += 1;
+        }
+
+        self.window_millis += delta_millis;
+        if self.window_millis >= 1000.0 {
+            self.fps = Some((self.window_frames as f64 * 1000.0 / self.window_millis) as f32);
+            self.window_millis = 0.0;
+            self.window_frames = 0;
+        }
+    }
+
+    pub(crate) fn fps(&self) -> f32 {
+        self.fps
+            .unwrap_or(self.target_fps.frames_per_second() as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_index_works() {
+        assert_eq!(Stat::from_index(7), Some(Stat::Fps));
+        assert_eq!(Stat::from_index(18), Some(Stat::Sfx(2)));
+        assert_eq!(Stat::from_index(53), Some(Stat::SfxNote(3)));
+        assert_eq!(Stat::from_index(34), Some(Stat::MouseButtons));
+        assert_eq!(Stat::from_index(80), Some(Stat::UtcTime(TimeField::Year)));
+        assert_eq!(
+            Stat::from_index(95),
+            Some(Stat::LocalTime(TimeField::Second))
+        );
+        assert_eq!(Stat::from_index(0), None);
+        assert_eq!(Stat::from_index(200), None);
+    }
+
+    #[test]
+    fn frame_stats_measure_fps_and_cpu_usage() {
+        let mut stats = FrameStats::new();
+        assert_eq!(stats.fps(), 30.0);
+
+        // Drawing every other tick, spending half of each frame updating.
+        for tick in 0..130 {
+            let updates = tick % 2;
+            stats.on_tick(Fps::Sixty, 1000.0 / 120.0, updates, 1000.0 / 120.0);
+        }
+
+        assert_eq!(stats.target_fps, Fps::Sixty);
+        assert_eq!(stats.frames, 65);
+        assert_eq!(stats.cpu_usage, 0.5);
+        assert!((stats.fps() - 60.0).abs() < 1.0, "{}", stats.fps());
+    }
+}
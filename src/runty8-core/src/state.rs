@@ -2,7 +2,7 @@ use crate::input::Input;
 use crate::Button;
 use ButtonState::*;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct State {
     left: ButtonState,
     right: ButtonState,
@@ -13,6 +13,15 @@ pub struct State {
     pub mouse_x: i32,
     pub mouse_y: i32,
     mouse_pressed: ButtonState,
+    mouse_right_pressed: ButtonState,
+    mouse_middle_pressed: ButtonState,
+    // Total scrolled up to the last frame, and how much of it happened in the last frame.
+    wheel_total: (i32, i32),
+    wheel_delta: (i32, i32),
+    // Characters typed up to the last frame, and the last one if it was typed in the last frame.
+    typed_chars: u32,
+    typed_char: Option<char>,
+    pause: ButtonState,
 }
 
 #[allow(clippy::new_without_default)]
@@ -28,6 +37,13 @@ impl State {
             mouse_x: 64,
             mouse_y: 64,
             mouse_pressed: NotPressed,
+            mouse_right_pressed: NotPressed,
+            mouse_middle_pressed: NotPressed,
+            wheel_total: (0, 0),
+            wheel_delta: (0, 0),
+            typed_chars: 0,
+            typed_char: None,
+            pause: NotPressed,
         }
     }
 
@@ -44,8 +60,32 @@ impl State {
         self.x.update(input.x);
         self.c.update(input.c);
         self.mouse_pressed.update(input.mouse);
+        self.mouse_right_pressed.update(input.mouse_right);
+        self.mouse_middle_pressed.update(input.mouse_middle);
         self.mouse_x = input.mouse_x;
         self.mouse_y = input.mouse_y;
+
+        self.wheel_delta = (
+            input.wheel_x - self.wheel_total.0,
+            input.wheel_y - self.wheel_total.1,
+        );
+        self.wheel_total = (input.wheel_x, input.wheel_y);
+
+        // If several characters were typed in a single frame only the last one is reported.
+        self.typed_char = if input.typed_chars != self.typed_chars {
+            input.last_typed_char
+        } else {
+            None
+        };
+        self.typed_chars = input.typed_chars;
+        self.pause.update(input.pause);
+    }
+
+    /// Whether the pause button (Enter or P) was pressed in the last frame.
+    ///
+    /// Like `btnp`, holding it down (and the OS repeating the key) doesn't count as new presses.
+    pub fn pause_pressed(&self) -> bool {
+        self.pause.btnp()
     }
 
     pub(crate) fn button(&self, button: Button) -> &ButtonState {
@@ -57,15 +97,40 @@ impl State {
             Button::X => &self.x,
             Button::C => &self.c,
             Button::Mouse => &self.mouse_pressed,
+            Button::MouseRight => &self.mouse_right_pressed,
+            Button::MouseMiddle => &self.mouse_middle_pressed,
         }
     }
 
     pub(crate) fn mouse(&self) -> (i32, i32) {
         (self.mouse_x, self.mouse_y)
     }
+
+    /// Bitmask of the held mouse buttons, like pico8's `stat(34)`:
+    /// 1 for left, 2 for right, 4 for middle.
+    pub(crate) fn mouse_buttons(&self) -> u8 {
+        [
+            &self.mouse_pressed,
+            &self.mouse_right_pressed,
+            &self.mouse_middle_pressed,
+        ]
+        .into_iter()
+        .enumerate()
+        .filter(|(_, button)| button.btn())
+        .fold(0, |mask, (bit, _)| mask | (1 << bit))
+    }
+
+    pub(crate) fn mouse_wheel(&self) -> (i32, i32) {
+        self.wheel_delta
+    }
+
+    /// The character typed in the last frame, like pico8's devkit keyboard (`stat(30)`, `stat(31)`).
+    pub(crate) fn typed_char(&self) -> Option<char> {
+        self.typed_char
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) enum ButtonState {
     JustPressed, // btn => true, btnp => true
     Held,        // btn => true, btnp => false
@@ -120,3 +185,70 @@ impl ButtonState {
         matches!(*self, JustPressed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InputEvent, Key, KeyState, KeyboardEvent, MouseButton, MouseEvent};
+
+    fn mouse_button(button: MouseButton, state: KeyState) -> InputEvent {
+        InputEvent::Mouse(MouseEvent::Button { button, state })
+    }
+
+    #[test]
+    fn mouse_buttons_bitmask_works() {
+        let mut input = Input::new();
+        let mut state = State::new();
+
+        input.on_event(mouse_button(MouseButton::Right, KeyState::Down));
+        input.on_event(mouse_button(MouseButton::Middle, KeyState::Down));
+        state.update_input(&input);
+
+        assert_eq!(state.mouse_buttons(), 6);
+        assert!(state.button(Button::MouseRight).btnp());
+        assert!(!state.button(Button::Mouse).btn());
+
+        input.on_event(mouse_button(MouseButton::Middle, KeyState::Up));
+        input.on_event(mouse_button(MouseButton::Left, KeyState::Down));
+        state.update_input(&input);
+
+        assert_eq!(state.mouse_buttons(), 3);
+        assert!(!state.button(Button::MouseRight).btnp());
+    }
+
+    #[test]
+    fn mouse_wheel_is_reported_once_per_frame() {
+        let mut input = Input::new();
+        let mut state = State::new();
+
+        input.on_event(InputEvent::Mouse(MouseEvent::Wheel {
+            delta_x: 0,
+            delta_y: 1,
+        }));
+        input.on_event(InputEvent::Mouse(MouseEvent::Wheel {
+            delta_x: -1,
+            delta_y: 1,
+        }));
+        state.update_input(&input);
+        assert_eq!(state.mouse_wheel(), (-1, 2));
+
+        state.update_input(&input);
+        assert_eq!(state.mouse_wheel(), (0, 0));
+    }
+
+    #[test]
+    fn typed_chars_are_reported_once() {
+        let mut input = Input::new();
+        let mut state = State::new();
+        let key = |key, state| InputEvent::Keyboard(KeyboardEvent { key, state });
+
+        input.on_event(key(Key::H, KeyState::Down));
+        input.on_event(key(Key::H, KeyState::Up));
+        input.on_event(key(Key::LeftArrow, KeyState::Down));
+        state.update_input(&input);
+        assert_eq!(state.typed_char(), Some('h'));
+
+        state.update_input(&input);
+        assert_eq!(state.typed_char(), None);
+    }
+}
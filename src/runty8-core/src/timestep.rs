@@ -0,0 +1,122 @@
+/// Frame rates supported by pico8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fps {
+    /// 30 frames per second, like pico8's `_update`.
+    Thirty,
+    /// 60 frames per second, like pico8's `_update60`.
+    Sixty,
+}
+
+impl Fps {
+    pub fn frames_per_second(self) -> u32 {
+        match self {
+            Fps::Thirty => 30,
+            Fps::Sixty => 60,
+        }
+    }
+
+    /// Duration of a single frame, in milliseconds.
+    pub fn delta_millis(self) -> f64 {
+        1000.0 / self.frames_per_second() as f64
+    }
+}
+
+/// Fixed timestep game loop logic.
+///
+/// Accumulates the (variable) time between ticks and decides how many
+/// (fixed duration) updates need to run to catch up.
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    delta_time: f64,
+    accumulated_delta: f64,
+}
+
+impl FixedTimestep {
+    /// Maximum amount of updates to run in a single tick.
+    ///
+    /// If the game falls further behind than this (because updates are too slow,
+    /// or the window was suspended), we drop the remaining time instead of trying
+    /// to catch up, which would only make us fall further behind.
+    pub const MAX_UPDATES_PER_TICK: u32 = 4;
+
+    pub fn new(fps: Fps) -> Self {
+        Self {
+            delta_time: fps.delta_millis(),
+            accumulated_delta: 0.0,
+        }
+    }
+
+    /// Advances time by `delta_millis`, returning how many updates should be run.
+    ///
+    /// Callers should draw once after running all the updates (and only if there was at least one),
+    /// skipping the draws of intermediate frames when falling behind, like pico8 does.
+    pub fn advance(&mut self, delta_millis: f64) -> u32 {
+        self.accumulated_delta += delta_millis;
+
+        let mut updates = 0;
+        while self.accumulated_delta >= self.delta_time {
+            if updates == Self::MAX_UPDATES_PER_TICK {
+                self.accumulated_delta %= self.delta_time;
+                break;
+            }
+
+            self.accumulated_delta -= self.delta_time;
+            updates += 1;
+        }
+
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_one_update_per_frame() {
+        let mut timestep = FixedTimestep::new(Fps::Thirty);
+
+        assert_eq!(timestep.advance(0.0), 0);
+        assert_eq!(timestep.advance(20.0), 0);
+        assert_eq!(timestep.advance(20.0), 1);
+        assert_eq!(timestep.advance(20.0), 0);
+        assert_eq!(timestep.advance(20.0), 1);
+    }
+
+    #[test]
+    fn sixty_fps_runs_twice_as_many_updates() {
+        let mut thirty = FixedTimestep::new(Fps::Thirty);
+        let mut sixty = FixedTimestep::new(Fps::Sixty);
+
+        let (mut thirty_updates, mut sixty_updates) = (0, 0);
+        // A bit over a second, so that floating point errors don't matter.
+        for _ in 0..101 {
+            thirty_updates += thirty.advance(10.0);
+            sixty_updates += sixty.advance(10.0);
+        }
+
+        assert_eq!(thirty_updates, 30);
+        assert_eq!(sixty_updates, 60);
+    }
+
+    #[test]
+    fn catches_up_when_falling_behind() {
+        let mut timestep = FixedTimestep::new(Fps::Sixty);
+
+        assert_eq!(timestep.advance(3.0 * Fps::Sixty.delta_millis() + 1.0), 3);
+        assert_eq!(timestep.advance(Fps::Sixty.delta_millis()), 1);
+    }
+
+    #[test]
+    fn caps_catch_up_updates() {
+        let mut timestep = FixedTimestep::new(Fps::Thirty);
+
+        assert_eq!(
+            timestep.advance(1000.0 + 10.0),
+            FixedTimestep::MAX_UPDATES_PER_TICK
+        );
+        // The rest of the backlog is dropped.
+        assert_eq!(timestep.advance(20.0), 0);
+        assert_eq!(timestep.advance(5.0), 1);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::ui::DrawFn;
 use crate::ui::Element;
 use crate::Resources;
-use runty8_core::{App, Event, Pico8};
+use runty8_core::{App, Event, FixedTimestep, Pico8};
 use std::fmt::Debug;
 
 /// An Elm-style app
@@ -37,6 +37,10 @@ impl<A: ElmApp> AppCompat for ElmAppCompat<A> {
     fn subscriptions(&self, event: &Event) -> Vec<Self::Msg> {
         self.app.subscriptions(event)
     }
+
+    fn snapshot(&self) -> Option<Self> {
+        None
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -46,31 +50,31 @@ pub enum Pico8AppMsg {
 
 pub(crate) struct Pico8AppCompat<A> {
     app: A,
-    accumulated_delta: f64,
-    delta_time: f64,
+    timestep: FixedTimestep,
 }
 
 impl<A: App> AppCompat for Pico8AppCompat<A> {
     type Msg = Pico8AppMsg;
 
     fn init(pico8: &mut Pico8) -> Self {
-        let fps = 30_f64;
-
         Self {
             app: A::init(pico8),
-            accumulated_delta: 0.0,
-            delta_time: 1000.0 / fps,
+            timestep: FixedTimestep::new(A::FPS),
         }
     }
 
     fn update(&mut self, msg: &Self::Msg, pico8: &mut Pico8) {
         let Pico8AppMsg::Tick { delta_millis } = *msg;
-        self.accumulated_delta += delta_millis;
+        let updates = self.timestep.advance(delta_millis);
+        let tick_start = instant::Instant::now();
 
-        while self.accumulated_delta > self.delta_time {
+        for _ in 0..updates {
             self.app.update(pico8);
-            self.accumulated_delta -= self.delta_time;
         }
+
+        // Drawing happens in `view`, so it isn't part of the measured CPU usage.
+        let busy_millis = tick_start.elapsed().as_secs_f64() * 1000.0;
+        pico8.record_tick(A::FPS, delta_millis, updates, busy_millis);
     }
 
     fn view(&mut self, _: &mut Resources) -> Element<'_, Self::Msg> {
@@ -84,6 +88,13 @@ impl<A: App> AppCompat for Pico8AppCompat<A> {
             vec![]
         }
     }
+
+    fn snapshot(&self) -> Option<Self> {
+        Some(Self {
+            app: self.app.snapshot()?,
+            timestep: self.timestep.clone(),
+        })
+    }
 }
 
 /// Not intended for direct use.
@@ -93,4 +104,8 @@ pub(crate) trait AppCompat {
     fn update(&mut self, msg: &Self::Msg, pico8: &mut Pico8);
     fn view(&mut self, resources: &mut Resources) -> Element<'_, Self::Msg>;
     fn subscriptions(&self, event: &Event) -> Vec<Self::Msg>;
+    /// A copy of the app's state for save states, if it supports them.
+    fn snapshot(&self) -> Option<Self>
+    where
+        Self: Sized;
 }
@@ -7,7 +7,9 @@ use crate::{
     ui::Element,
     Resources,
 };
-use runty8_core::{Event, Input, InputEvent, Key, KeyboardEvent, MouseEvent, Pico8};
+use runty8_core::{
+    Event, Input, InputEvent, Key, KeyState, KeyboardEvent, MouseEvent, Pico8, Pico8Snapshot,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum Msg<AppMsg> {
@@ -15,13 +17,16 @@ pub(crate) enum Msg<AppMsg> {
     App(AppMsg),
     KeyboardEvent(KeyboardEvent),
     MouseEvent(MouseEvent),
-    Tick,
+    Tick { delta_millis: f64 },
+    WindowClosed,
 }
 
 #[derive(Copy, Clone, Debug)]
 enum KeyComboAction {
     RestartGame,
     SwitchScene,
+    SaveState,
+    LoadState,
 }
 
 #[derive(Debug)]
@@ -32,6 +37,12 @@ pub(crate) struct Controller<Game> {
     key_combos: KeyCombos<KeyComboAction>,
     keys: Input,
     pico8: Pico8,
+    save_state: Option<(Game, Pico8Snapshot)>,
+    // Save unsaved changes when quitting, instead of asking.
+    autosave: bool,
+    // The window title, as set by the game, and whether it's marked as having unsaved changes.
+    title: String,
+    title_marked_unsaved: bool,
 }
 
 impl<T> Controller<T> {
@@ -39,24 +50,60 @@ impl<T> Controller<T> {
         self.pico8.draw_data.buffer()
     }
 
+    /// The window's title if it changed, with an asterisk when there are unsaved changes.
     pub(crate) fn take_new_title(&mut self) -> Option<String> {
-        self.pico8.take_new_title()
+        let new_title = self.pico8.take_new_title();
+        let unsaved_changes = self.editor.has_unsaved_changes();
+
+        if new_title.is_none() && unsaved_changes == self.title_marked_unsaved {
+            return None;
+        }
+
+        if let Some(new_title) = new_title {
+            self.title = new_title;
+        }
+        self.title_marked_unsaved = unsaved_changes;
+
+        Some(if unsaved_changes {
+            format!("{}*", self.title)
+        } else {
+            self.title.clone()
+        })
+    }
+
+    /// Whether the window was closed (and unsaved changes handled), so the event loop should exit.
+    pub(crate) fn should_exit(&self) -> bool {
+        self.editor.is_quitting()
     }
 }
 
 impl<Game: AppCompat> Controller<Game> {
-    pub fn init(scene: Scene, resources: Resources) -> Self {
+    pub fn init(scene: Scene, resources: Resources, autosave: bool) -> Self {
+        let mut editor = <Editor as ElmApp>::init();
+        editor.enable_backups(&resources);
+        // Show the editor if it's asking whether to restore a backup, like when quitting.
+        let scene = if editor.is_prompting() {
+            Scene::Editor
+        } else {
+            scene
+        };
         let mut pico8 = Pico8::new(resources);
 
         Self {
             scene,
-            editor: <Editor as ElmApp>::init(),
+            editor,
             app: Game::init(&mut pico8),
             key_combos: KeyCombos::new()
                 .push(KeyComboAction::RestartGame, Key::R, &[Key::Control])
-                .push(KeyComboAction::SwitchScene, Key::Escape, &[]),
+                .push(KeyComboAction::SwitchScene, Key::Escape, &[])
+                .push(KeyComboAction::SaveState, Key::S, &[Key::Alt])
+                .push(KeyComboAction::LoadState, Key::L, &[Key::Alt]),
             keys: Input::new(),
             pico8,
+            save_state: None,
+            autosave,
+            title: "Runty8".to_owned(),
+            title_marked_unsaved: false,
         }
     }
 
@@ -70,12 +117,27 @@ impl<Game: AppCompat> Controller<Game> {
             }
             &Msg::MouseEvent(event) => self.keys.on_event(InputEvent::Mouse(event)),
 
+            &Msg::KeyboardEvent(KeyboardEvent {
+                key,
+                state: KeyState::Down,
+            }) if self.editor.is_prompting() => {
+                self.editor.answer_prompt(key, &mut self.pico8.resources);
+            }
             &Msg::KeyboardEvent(event) => {
                 self.handle_key_combos(event);
                 self.keys.on_event(InputEvent::Keyboard(event));
             }
-            &Msg::Tick => {
+            &Msg::Tick { delta_millis } => {
                 self.pico8.state.update_input(&self.keys);
+                // Even while playing, since that's when crashes are most likely.
+                self.editor
+                    .tick_backups(delta_millis, &self.pico8.resources);
+            }
+            &Msg::WindowClosed => {
+                // Show the editor, in case it has to ask about unsaved changes.
+                self.scene = Scene::Editor;
+                self.editor
+                    .request_quit(&self.pico8.resources, self.autosave);
             }
         }
     }
@@ -98,8 +160,8 @@ impl<Game: AppCompat> Controller<Game> {
             Event::Input(InputEvent::Keyboard(keyboard_event)) => {
                 Some(Msg::KeyboardEvent(*keyboard_event))
             }
-            Event::Tick { .. } => Some(Msg::Tick),
-            Event::WindowClosed => todo!("WindowClosed event not yet handled"),
+            &Event::Tick { delta_millis } => Some(Msg::Tick { delta_millis }),
+            Event::WindowClosed => Some(Msg::WindowClosed),
         }
         .into_iter();
 
@@ -123,10 +185,33 @@ impl<Game: AppCompat> Controller<Game> {
     fn handle_key_combos(&mut self, key_event: KeyboardEvent) {
         self.key_combos.on_event(key_event, |action| match action {
             KeyComboAction::RestartGame => {
+                self.pico8.reset_time();
                 self.app = Game::init(&mut self.pico8);
                 self.scene = Scene::App;
             }
             KeyComboAction::SwitchScene => self.scene.flip(),
+            KeyComboAction::SaveState => match self.app.snapshot() {
+                Some(app) => {
+                    self.save_state = Some((app, self.pico8.snapshot()));
+                    self.editor.on_save_state();
+                    self.editor.alert("SAVED STATE");
+                }
+                // See `App::snapshot`.
+                None => self.editor.alert("SAVE STATES AREN'T SUPPORTED"),
+            },
+            KeyComboAction::LoadState => {
+                if let Some((app, pico8)) = &self.save_state {
+                    // Snapshot again, so that the save state can be loaded more than once.
+                    if let Some(app) = app.snapshot() {
+                        let current = self.pico8.resources.clone();
+                        self.app = app;
+                        self.pico8.restore(pico8);
+                        self.editor
+                            .keep_edits_since_save_state(current, &mut self.pico8.resources);
+                        self.scene = Scene::App;
+                    }
+                }
+            }
         });
     }
 
@@ -173,3 +258,85 @@ impl Scene {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Pico8AppCompat;
+    use runty8_core::App;
+
+    #[derive(Clone)]
+    struct Counter {
+        frames: u32,
+    }
+
+    impl App for Counter {
+        fn init(_: &mut Pico8) -> Self {
+            Self { frames: 0 }
+        }
+
+        fn update(&mut self, _: &mut Pico8) {
+            self.frames += 1;
+        }
+
+        fn draw(&mut self, _: &mut Pico8) {}
+
+        fn snapshot(&self) -> Option<Self> {
+            Some(self.clone())
+        }
+    }
+
+    fn press(controller: &mut Controller<Pico8AppCompat<Counter>>, keys: &[Key]) {
+        for &key in keys {
+            controller.step(Event::Input(InputEvent::Keyboard(KeyboardEvent {
+                key,
+                state: KeyState::Down,
+            })));
+        }
+        for &key in keys.iter().rev() {
+            controller.step(Event::Input(InputEvent::Keyboard(KeyboardEvent {
+                key,
+                state: KeyState::Up,
+            })));
+        }
+    }
+
+    #[test]
+    fn loading_a_state_keeps_the_edited_resources() {
+        let resources = Resources::empty_in_temp_dir("controller");
+        let mut controller =
+            Controller::<Pico8AppCompat<Counter>>::init(Scene::App, resources, false);
+
+        controller.pico8.resources.sprite_sheet.set(0, 0, 7);
+        let cell = controller.pico8.mget(1, 1);
+
+        press(&mut controller, &[Key::Alt, Key::S]);
+        assert!(controller.save_state.is_some());
+        // The game changes the map, and then the sprite is shifted down in the editor.
+        controller.pico8.mset(1, 1, cell + 1);
+        press(&mut controller, &[Key::Escape]);
+        press(&mut controller, &[Key::S]);
+        press(&mut controller, &[Key::Alt, Key::L]);
+
+        assert_eq!(controller.pico8.mget(1, 1), cell);
+        assert_eq!(controller.pico8.resources.sprite_sheet.get(0, 0), 0);
+        assert_eq!(controller.pico8.resources.sprite_sheet.get(0, 1), 7);
+    }
+
+    #[test]
+    fn asks_to_restore_backups_from_the_editor() {
+        let resources = Resources::empty_in_temp_dir("controller-backups");
+        // A backup made 1ms after the epoch, newer than the (missing) assets.
+        let backups_path = format!("{}.backups", resources.assets_path);
+        std::fs::create_dir_all(format!("{backups_path}/1")).unwrap();
+
+        let mut controller =
+            Controller::<Pico8AppCompat<Counter>>::init(Scene::App, resources, false);
+        std::fs::remove_dir_all(&backups_path).unwrap();
+
+        assert!(matches!(controller.scene, Scene::Editor));
+        assert!(controller.editor.is_prompting());
+        press(&mut controller, &[Key::N]);
+        assert!(!controller.editor.is_prompting());
+    }
+}
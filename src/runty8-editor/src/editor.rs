@@ -1,9 +1,12 @@
+mod backup;
 mod brush_size;
+mod clipboard;
 pub mod key_combo;
 mod map;
 mod notification;
 mod sprite;
 mod undo_redo;
+mod zoom;
 
 use crate::app::ElmApp;
 use crate::editor::notification::Notification;
@@ -18,11 +21,15 @@ use brush_size::BrushSize;
 use runty8_core::InputEvent;
 use runty8_core::{
     serialize::{Ppm, Serialize},
-    Color, Event, Flags, Key, KeyState, KeyboardEvent, Map, Resources, Sprite, SpriteSheet,
+    Color, Event, Flags, ImportOptions, Key, KeyState, KeyboardEvent, Map, MouseButton, MouseEvent,
+    Resources, Sprite, SpriteSheet,
 };
 
+use self::backup::{Backup, Backups};
 use self::key_combo::KeyCombos;
 use self::undo_redo::{Command, Commands};
+use crate::util::vec2::{vec2, Vec2i};
+use std::path::Path;
 
 #[derive(Debug)]
 pub(crate) struct Editor {
@@ -33,18 +40,83 @@ pub(crate) struct Editor {
     map_button_state: button::State,
     tab_buttons: [button::State; 4],
     sprite_buttons: Vec<button::State>,
-    selected_tool: usize,
     tool_buttons: Vec<button::State>,
+    map_tool_buttons: Vec<button::State>,
     bottom_bar_text: String,
     notification: notification::State,
+    clipboard: clipboard::Clipboard,
     key_combos: KeyCombos<KeyComboAction>,
-    clipboard: Clipboard,
     commands: Commands,
     editor_sprites: SpriteSheet,
     map_editor: map::Editor,
     sprite_editor: sprite::Editor,
     brush_size: BrushSize,
     selected_sprite: usize,
+    // How many sprites are selected (horizontally and vertically), starting from `selected_sprite`.
+    selected_sprites_size: (usize, usize),
+    // The sprite the mouse was pressed on, while selecting sprites by dragging.
+    selecting_sprites_from: Option<usize>,
+    dirty: Dirty,
+    // Which resources were edited since the game's state was last saved,
+    // see [`Editor::keep_edits_since_save_state`].
+    edited_since_save_state: Dirty,
+    prompt: Option<Prompt>,
+    quitting: bool,
+    backups: Option<Backups>,
+}
+
+/// A yes/no question shown in the notification bar, see [`Editor::answer_prompt`].
+#[derive(Debug)]
+enum Prompt {
+    SaveBeforeQuitting,
+    RestoreBackup(Backup),
+}
+
+/// Which resources were edited since they were last saved.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+struct Dirty {
+    sprite_sheet: bool,
+    flags: bool,
+    map: bool,
+}
+
+impl Dirty {
+    const ALL: Self = Self {
+        sprite_sheet: true,
+        flags: true,
+        map: true,
+    };
+
+    fn any(self) -> bool {
+        self.sprite_sheet || self.flags || self.map
+    }
+
+    fn edited_by(command: &Command) -> Self {
+        let mut dirty = Self::default();
+        dirty.mark_edited_by(command);
+
+        dirty
+    }
+
+    fn add(&mut self, other: Dirty) {
+        self.sprite_sheet |= other.sprite_sheet;
+        self.flags |= other.flags;
+        self.map |= other.map;
+    }
+
+    /// Marks the resource `command` edits.
+    fn mark_edited_by(&mut self, command: &Command) {
+        match command {
+            Command::PixelChanged(_) => self.sprite_sheet = true,
+            Command::MapCellChanged(_) => self.map = true,
+            Command::FlagChanged(_) => self.flags = true,
+            Command::Group(commands) => {
+                for command in commands {
+                    self.mark_edited_by(command);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -79,10 +151,8 @@ pub(crate) enum Msg {
     SpriteButtonClicked(usize),
     FlagToggled(usize),
     FlagHovered { bit_number: usize },
-    SpriteEdited { x: usize, y: usize, color: Color }, // TODO: Improve
-    ToolSelected(usize),
-    ClickedMapTile { x: usize, y: usize },
     KeyboardEvent(KeyboardEvent),
+    MouseReleased,
     BrushSizeSliderHovered,
     BrushSizeSelected(BrushSize),
     MapEditorMsg(map::Msg),
@@ -90,126 +160,490 @@ pub(crate) enum Msg {
 }
 
 impl Editor {
-    fn shift_sprite(&mut self, shift_direction: ShiftDirection, sprite_sheet: &mut SpriteSheet) {
-        let sprite = sprite_sheet.get_sprite_mut(self.selected_sprite);
-        shift_direction.shift(sprite);
+    /// Transforms the pixels in the canvas (or the selected ones), as a single undoable change.
+    fn transform_pixels(
+        &mut self,
+        sprite_sheet: &mut SpriteSheet,
+        transform: impl FnOnce(&sprite::Pixels) -> sprite::Pixels,
+    ) {
+        let edits = self.sprite_editor.transform(sprite_sheet, transform);
+
+        self.apply_sprite_transform(&edits, sprite_sheet);
+    }
+
+    /// Applies the changes of a transform as a single undoable change.
+    fn apply_sprite_transform(
+        &mut self,
+        edits: &[sprite::PixelEdit],
+        sprite_sheet: &mut SpriteSheet,
+    ) {
+        self.apply_sprite_edits(edits, sprite_sheet);
+        self.commands.close_group();
     }
 
     fn handle_key_combos(&mut self, key_event: KeyboardEvent, resources: &mut Resources) {
-        self.key_combos.on_event(key_event, |action| {
-            handle_key_combo(
-                action,
-                self.selected_sprite,
-                &mut self.notification,
-                &mut self.clipboard,
-                resources,
-                &mut self.commands,
-                &mut self.tab,
-            );
-        })
+        let mut actions = vec![];
+        self.key_combos
+            .on_event(key_event, |action| actions.push(action));
+
+        for action in actions {
+            self.handle_key_combo(action, resources);
+        }
     }
-}
 
-#[derive(Debug)]
-struct Clipboard {
-    data: Vec<Color>,
-}
-impl Clipboard {
-    fn new() -> Self {
-        Self { data: vec![0; 64] }
+    /// Puts `text` in the system clipboard, alerting `copied` (or that it couldn't be copied).
+    fn copy_to_clipboard(&mut self, text: String, copied: String) {
+        match self.clipboard.set_text(text) {
+            Ok(()) => self.notification.alert(copied),
+            Err(err) => {
+                log::warn!("[Editor] Couldn't copy to the clipboard: {err}");
+                self.notification.alert("COULDN'T COPY".to_owned());
+            }
+        }
     }
 
-    fn copy_sprite(&mut self, sprite: &Sprite) {
-        self.data = sprite.to_owned();
+    fn handle_key_combo(&mut self, key_combo: KeyComboAction, resources: &mut Resources) {
+        match key_combo {
+            KeyComboAction::Copy if self.tab == Tab::MapEditor => {
+                match self.map_editor.copy(&resources.map) {
+                    Some(tiles) => self.copy_to_clipboard(
+                        tiles.to_clipboard_text(),
+                        format!("COPIED {} X {} TILES", tiles.width(), tiles.height()),
+                    ),
+                    None => self.notification.alert("NOTHING SELECTED".to_owned()),
+                }
+            }
+            KeyComboAction::Paste if self.tab == Tab::MapEditor => {
+                // Tiles copied from pico8 (or another runty8) replace the ones copied here.
+                if let Ok(tiles) = self
+                    .clipboard
+                    .get_text()
+                    .and_then(|text| map::Tiles::from_clipboard_text(&text))
+                {
+                    self.map_editor.set_clipboard(tiles);
+                }
+                let edits = self.map_editor.paste();
+
+                self.apply_map_edits(&edits, &mut resources.map);
+                self.commands.close_group();
+            }
+            KeyComboAction::Copy => {
+                let pixels = self.sprite_editor.copy(&resources.sprite_sheet);
+
+                self.copy_to_clipboard(
+                    pixels.to_clipboard_text(),
+                    format!("COPIED {} X {} PIXELS", pixels.width(), pixels.height()),
+                );
+            }
+            KeyComboAction::Paste => {
+                if let Ok(pixels) = self
+                    .clipboard
+                    .get_text()
+                    .and_then(|text| sprite::Pixels::from_clipboard_text(&text))
+                {
+                    self.sprite_editor.set_clipboard(pixels);
+                }
+                let edits = self.sprite_editor.paste();
+
+                self.apply_sprite_edits(&edits, &mut resources.sprite_sheet);
+                self.commands.close_group();
+            }
+            KeyComboAction::FlipVertically => {
+                self.transform_pixels(
+                    &mut resources.sprite_sheet,
+                    sprite::Pixels::flipped_vertically,
+                );
+            }
+            KeyComboAction::FlipHorizontally => {
+                self.transform_pixels(
+                    &mut resources.sprite_sheet,
+                    sprite::Pixels::flipped_horizontally,
+                );
+            }
+            KeyComboAction::Rotate if self.tab == Tab::SpriteEditor => {
+                match self.sprite_editor.rotate(&resources.sprite_sheet) {
+                    Some(edits) => self.apply_sprite_transform(&edits, &mut resources.sprite_sheet),
+                    None => self
+                        .notification
+                        .alert("ONLY SQUARES CAN BE ROTATED".to_owned()),
+                }
+            }
+            KeyComboAction::Outline if self.tab == Tab::SpriteEditor => {
+                let edits = self.sprite_editor.outline(&resources.sprite_sheet);
+
+                self.apply_sprite_transform(&edits, &mut resources.sprite_sheet);
+            }
+            KeyComboAction::ScaleUp | KeyComboAction::ScaleDown
+                if self.tab == Tab::SpriteEditor =>
+            {
+                let scale_up = matches!(key_combo, KeyComboAction::ScaleUp);
+                let edits = self.sprite_editor.scale(&resources.sprite_sheet, scale_up);
+
+                self.apply_sprite_transform(&edits, &mut resources.sprite_sheet);
+            }
+            // Only sprites can be transformed this way.
+            KeyComboAction::Rotate
+            | KeyComboAction::Outline
+            | KeyComboAction::ScaleUp
+            | KeyComboAction::ScaleDown => {}
+            KeyComboAction::Import | KeyComboAction::ImportDithered => {
+                let options = ImportOptions {
+                    dither: matches!(key_combo, KeyComboAction::ImportDithered),
+                    alpha_as_transparent: true,
+                };
+
+                self.import_png(resources, options);
+            }
+            KeyComboAction::Undo => {
+                if let Some(command) = self.commands.undo(&mut self.notification, resources) {
+                    let edited = Dirty::edited_by(command);
+                    self.mark_edited(edited);
+                }
+            }
+            KeyComboAction::Redo => {
+                if let Some(command) = self.commands.redo(&mut self.notification, resources) {
+                    let edited = Dirty::edited_by(command);
+                    self.mark_edited(edited);
+                }
+            }
+            KeyComboAction::Save => {
+                self.save(resources);
+            }
+            KeyComboAction::PreviousTab => {
+                self.show_tab(self.tab.previous());
+            }
+            KeyComboAction::NextTab => {
+                self.show_tab(self.tab.next());
+            }
+            KeyComboAction::SwitchMapMode if self.tab == Tab::MapEditor => {
+                self.map_editor
+                    .update(map::Msg::SwitchMapMode, &resources.map, &self.map_brush());
+            }
+            KeyComboAction::SwitchMapMode => {}
+            KeyComboAction::Shift(shift_direction) => {
+                self.transform_pixels(&mut resources.sprite_sheet, |pixels| {
+                    pixels.shifted(shift_direction.offset())
+                });
+            }
+        }
     }
 
-    fn paste_into(&self, sprite: &mut Sprite) {
-        for (sprite_pixel, clipboard_pixel) in sprite.iter_mut().zip(self.data.iter().copied()) {
-            *sprite_pixel = clipboard_pixel;
+    /// Imports [`IMPORTED_IMAGE`] (from the assets folder) into the selected pixels
+    /// (or the whole sprite sheet).
+    fn import_png(&mut self, resources: &mut Resources, options: ImportOptions) {
+        let path = Path::new(&resources.assets_path).join(IMPORTED_IMAGE);
+        let edits = std::fs::read(&path)
+            .map_err(|err| format!("[Editor] Couldn't read {}: {err}", path.display()))
+            .and_then(|png| {
+                self.sprite_editor
+                    .import_png(&resources.sprite_sheet, &png, options)
+            });
+
+        match edits {
+            Ok(edits) => {
+                self.apply_sprite_transform(&edits, &mut resources.sprite_sheet);
+                self.notification
+                    .alert(format!("IMPORTED {}", IMPORTED_IMAGE.to_uppercase()));
+            }
+            Err(err) => {
+                log::warn!("{err}");
+                self.notification
+                    .alert(format!("COULDN'T IMPORT {}", IMPORTED_IMAGE.to_uppercase()));
+            }
         }
     }
-}
 
-fn handle_key_combo(
-    key_combo: KeyComboAction,
-    selected_sprite: usize,
-    notification: &mut notification::State,
-    clipboard: &mut Clipboard,
-    resources: &mut Resources,
-    commands: &mut Commands,
-    tab: &mut Tab,
-) {
-    match key_combo {
-        KeyComboAction::Copy => {
-            let sprite = resources.sprite_sheet.get_sprite(selected_sprite);
-            notification.alert("COPIED 1 X 1 SPRITES".to_owned());
-            clipboard.copy_sprite(sprite);
+    /// The selected sprites, which the map tools paint with.
+    fn map_brush(&self) -> map::Tiles {
+        let (width, height) = self.selected_sprites_size;
+
+        map::Tiles::from_sprite_sheet(self.selected_sprite, width, height)
+    }
+
+    fn apply_map_edits(&mut self, edits: &[map::TileEdit], map: &mut Map) {
+        for edit in edits {
+            let previous_sprite = map.mget(edit.x as i32, edit.y as i32);
+            if previous_sprite == edit.sprite {
+                continue;
+            }
+
+            self.commands.push_grouped(Command::map_cell_changed(
+                edit.x,
+                edit.y,
+                previous_sprite,
+                edit.sprite,
+            ));
+            map.mset(edit.x, edit.y, edit.sprite);
+            self.mark_edited(Dirty {
+                map: true,
+                ..Dirty::default()
+            });
         }
-        KeyComboAction::Paste => {
-            let sprite = resources.sprite_sheet.get_sprite_mut(selected_sprite);
-            notification.alert("PASTED 1 X 1 SPRITES".to_owned());
+    }
 
-            clipboard.paste_into(sprite);
+    fn apply_sprite_edits(&mut self, edits: &[sprite::PixelEdit], sprite_sheet: &mut SpriteSheet) {
+        for edit in edits {
+            let previous_color = sprite_sheet.get(edit.x, edit.y);
+            if previous_color == edit.color {
+                continue;
+            }
+
+            let sprite =
+                edit.y / Sprite::HEIGHT * SpriteSheet::SPRITES_PER_ROW + edit.x / Sprite::WIDTH;
+            self.commands.push_grouped(Command::pixel_changed(
+                sprite,
+                (edit.x % Sprite::WIDTH) as isize,
+                (edit.y % Sprite::HEIGHT) as isize,
+                previous_color,
+                edit.color,
+            ));
+            sprite_sheet.set(edit.x, edit.y, edit.color);
+            self.mark_edited(Dirty {
+                sprite_sheet: true,
+                ..Dirty::default()
+            });
         }
-        KeyComboAction::FlipVertically => {
-            let sprite = resources.sprite_sheet.get_sprite_mut(selected_sprite);
+    }
 
-            sprite.flip_vertically()
+    fn show_tab(&mut self, tab: Tab) {
+        self.tab = tab;
+
+        if tab == Tab::SpriteEditor {
+            self.sprite_editor.show_sprite(self.selected_sprite);
+            self.select_canvas_sprites();
+        }
+    }
+
+    /// Selects the sprites shown in the sprite editor's canvas.
+    fn select_canvas_sprites(&mut self) {
+        let (sprite, size) = self.sprite_editor.canvas_sprites();
+
+        self.selected_sprite = sprite;
+        self.selected_sprites_size = size;
+    }
+
+    /// Selects a rectangle of sprites, from the one the mouse was pressed on to `sprite`.
+    /// In the sprite editor, selects the block of sprites that fits in the canvas instead.
+    fn select_sprites(&mut self, sprite: usize) {
+        if self.tab == Tab::SpriteEditor {
+            self.sprite_editor.show_sprite(sprite);
+            self.select_canvas_sprites();
+            return;
+        }
+
+        let from = *self.selecting_sprites_from.get_or_insert(sprite);
+        let row = |sprite| sprite / SpriteSheet::SPRITES_PER_ROW;
+        let col = |sprite| sprite % SpriteSheet::SPRITES_PER_ROW;
+
+        let (top, bottom) = (row(from).min(row(sprite)), row(from).max(row(sprite)));
+        let (left, right) = (col(from).min(col(sprite)), col(from).max(col(sprite)));
+
+        self.selected_sprite = top * SpriteSheet::SPRITES_PER_ROW + left;
+        self.selected_sprites_size = (right - left + 1, bottom - top + 1);
+    }
+
+    /// Returns whether everything got saved. If a file couldn't be written, the changes are still
+    /// considered unsaved, and the backups are kept.
+    fn save(&mut self, resources: &Resources) -> bool {
+        let to_serialize = changed_files(resources, self.dirty);
+
+        if to_serialize.is_empty() {
+            self.notification.alert("NOTHING TO SAVE".to_owned());
+            return true;
+        }
+
+        let written = to_serialize.iter().try_for_each(|(name, serializable)| {
+            runty8_core::serialize::serialize(&resources.assets_path, name, &serializable.as_ref())
+        });
+
+        if written.is_err() {
+            self.notification.alert("COULDN'T SAVE".to_owned());
+            return false;
         }
-        KeyComboAction::FlipHorizontally => {
-            let sprite = resources.sprite_sheet.get_sprite_mut(selected_sprite);
 
-            sprite.flip_horizontally()
+        self.notification.alert("SAVED".to_owned());
+        self.dirty = Dirty::default();
+        self.clear_backups();
+
+        true
+    }
+
+    // The backups are only needed while there are unsaved changes.
+    fn clear_backups(&mut self) {
+        if let Some(Err(_)) = self.backups.as_mut().map(Backups::clear) {
+            self.notification
+                .alert("COULDN'T DELETE BACKUPS".to_owned());
         }
-        KeyComboAction::Undo => {
-            commands.undo(notification, &mut resources.sprite_sheet);
+    }
+
+    fn mark_edited(&mut self, edited: Dirty) {
+        self.dirty.add(edited);
+        self.edited_since_save_state.add(edited);
+    }
+
+    /// Starts keeping track of the resources edited from now on, when the game's state is saved.
+    pub(crate) fn on_save_state(&mut self) {
+        self.edited_since_save_state = Dirty::default();
+    }
+
+    /// After loading a save state (into `restored`), brings back the resources edited since it was
+    /// saved from `current`, so that loading a state doesn't undo work done in the editor.
+    pub(crate) fn keep_edits_since_save_state(&self, current: Resources, restored: &mut Resources) {
+        let edited = self.edited_since_save_state;
+
+        if edited.sprite_sheet {
+            restored.sprite_sheet = current.sprite_sheet;
         }
-        KeyComboAction::Redo => {
-            commands.redo(notification, &mut resources.sprite_sheet);
+        if edited.flags {
+            restored.sprite_flags = current.sprite_flags;
         }
-        KeyComboAction::Save => {
-            save(notification, resources);
+        if edited.map {
+            restored.map = current.map;
         }
-        KeyComboAction::PreviousTab => {
-            *tab = tab.previous();
+    }
+
+    /// Whether the sprites, flags or map were edited since they were last saved.
+    pub(crate) fn has_unsaved_changes(&self) -> bool {
+        self.dirty.any()
+    }
+
+    /// Starts quitting: right away if there's nothing to save (or `autosave` is set, after saving),
+    /// otherwise after asking the user whether to save, see [`Editor::answer_prompt`].
+    pub(crate) fn request_quit(&mut self, resources: &Resources, autosave: bool) {
+        if self.dirty.any() && autosave {
+            self.save(resources);
         }
-        KeyComboAction::NextTab => {
-            *tab = tab.next();
+
+        if self.dirty.any() {
+            self.prompt(Prompt::SaveBeforeQuitting, "SAVE CHANGES? Y/N/ESC");
+        } else {
+            self.quitting = true;
         }
     }
-}
 
-fn save(notification: &mut notification::State, resources: &Resources) {
-    notification.alert("SAVED".to_owned());
+    fn prompt(&mut self, prompt: Prompt, question: &str) {
+        self.notification.prompt(question.to_owned());
+        self.prompt = Some(prompt);
+    }
 
-    let map_ppm = Ppm::from_map(&resources.map, &resources.sprite_sheet);
-    let sprite_sheet_ppm = Ppm::from_sprite_sheet(&resources.sprite_sheet);
-    let to_serialize: &[(&str, &dyn Serialize)] = &[
-        (&Flags::file_name(), &resources.sprite_flags),
-        (&SpriteSheet::file_name(), &resources.sprite_sheet),
-        (&Map::file_name(), &resources.map),
-        ("map.ppm", &map_ppm),
-        ("sprite_sheet.ppm", &sprite_sheet_ppm),
-    ];
+    /// Shows `text` in the notification bar, for a while.
+    pub(crate) fn alert(&mut self, text: &str) {
+        self.notification.alert(text.to_owned());
+    }
 
-    for (name, serializable) in to_serialize.iter() {
-        runty8_core::serialize::serialize(&resources.assets_path, name, serializable);
+    pub(crate) fn is_prompting(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// Y and N answer the prompt, Escape dismisses it (e.g, to keep editing instead of quitting).
+    pub(crate) fn answer_prompt(&mut self, key: Key, resources: &mut Resources) {
+        let prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+
+        match (prompt, key) {
+            (Prompt::SaveBeforeQuitting, Key::Y) => {
+                self.quitting = self.save(resources);
+            }
+            (Prompt::SaveBeforeQuitting, Key::N) => {
+                self.clear_backups();
+                self.quitting = true;
+            }
+            (Prompt::RestoreBackup(backup), Key::Y) => match backup.restore(resources) {
+                Ok(()) => {
+                    self.notification.alert("RESTORED BACKUP".to_owned());
+                    // The restored resources differ from the saved ones.
+                    self.mark_edited(Dirty::ALL);
+                }
+                Err(_) => self
+                    .notification
+                    .alert("COULDN'T RESTORE BACKUP".to_owned()),
+            },
+            (_, Key::N | Key::Escape) => self.notification.dismiss(),
+            (prompt, _) => self.prompt = Some(prompt),
+        }
+    }
+
+    /// Starts backing up unsaved changes periodically (next to the assets),
+    /// offering to restore the newest backup if it's newer than the saved assets.
+    pub(crate) fn enable_backups(&mut self, resources: &Resources) {
+        let backups = Backups::next_to(&resources.assets_path);
+
+        if let Some(backup) = backups.newer_than_assets(&resources.assets_path) {
+            self.prompt(Prompt::RestoreBackup(backup), "RESTORE NEWER BACKUP? Y/N");
+        }
+
+        self.backups = Some(backups);
+    }
+
+    /// Backs up unsaved changes, every once in a while.
+    pub(crate) fn tick_backups(&mut self, delta_millis: f64, resources: &Resources) {
+        if let (true, Some(backups)) = (self.dirty.any(), &mut self.backups) {
+            if backups.tick(delta_millis, resources).is_err() {
+                self.notification.alert("COULDN'T BACK UP".to_owned());
+            }
+        }
+    }
+
+    /// Whether the editor is done, and the event loop should exit.
+    pub(crate) fn is_quitting(&self) -> bool {
+        self.quitting
     }
 }
 
+/// The files that need to be rewritten after editing the `dirty` resources.
+fn changed_files(resources: &Resources, dirty: Dirty) -> Vec<(String, Box<dyn Serialize + '_>)> {
+    let mut files: Vec<(String, Box<dyn Serialize>)> = vec![];
+
+    if dirty.flags {
+        files.push((Flags::file_name(), Box::new(&resources.sprite_flags)));
+    }
+    if dirty.sprite_sheet {
+        files.push((SpriteSheet::file_name(), Box::new(&resources.sprite_sheet)));
+        files.push((
+            "sprite_sheet.ppm".to_owned(),
+            Box::new(Ppm::from_sprite_sheet(&resources.sprite_sheet)),
+        ));
+    }
+    if dirty.map {
+        files.push((Map::file_name(), Box::new(&resources.map)));
+    }
+    // The map's image is drawn with the sprites.
+    if dirty.map || dirty.sprite_sheet {
+        files.push((
+            "map.ppm".to_owned(),
+            Box::new(Ppm::from_map(&resources.map, &resources.sprite_sheet)),
+        ));
+    }
+
+    files
+}
+
 #[derive(Copy, Clone, Debug)]
 enum KeyComboAction {
     Copy,
     Paste,
     FlipVertically,
     FlipHorizontally,
+    Rotate,
+    Outline,
+    ScaleUp,
+    ScaleDown,
+    Import,
+    ImportDithered,
     Undo,
     Redo,
     Save,
     PreviousTab,
     NextTab,
+    SwitchMapMode,
+    Shift(ShiftDirection),
 }
 
+/// The image imported into the sprite sheet (with Ctrl+I), from the assets folder.
+const IMPORTED_IMAGE: &str = "import.png";
+
 fn load_editor_sprite_sheet() -> Result<SpriteSheet, String> {
     let editor_sprites = std::fs::read_to_string("./src/editor/sprite_sheet.txt")
         .map_err(|_| "Couldn't find editor sprite sheet file.".to_owned())?;
@@ -235,21 +669,38 @@ impl ElmApp for Editor {
                 button::State::new(),
             ],
             sprite_buttons: vec![button::State::new(); 64],
-            selected_tool: 0,
-            tool_buttons: vec![button::State::new(); 2],
+            tool_buttons: vec![button::State::new(); sprite::Tool::ALL.len()],
+            map_tool_buttons: vec![button::State::new(); map::Tool::ALL.len()],
             bottom_bar_text: "".to_owned(),
             notification: notification::State::new(),
+            clipboard: clipboard::Clipboard::new(),
             key_combos: KeyCombos::new()
                 .push(KeyComboAction::Copy, Key::C, &[Key::Control])
                 .push(KeyComboAction::Paste, Key::V, &[Key::Control])
                 .push(KeyComboAction::Undo, Key::Z, &[Key::Control])
                 .push(KeyComboAction::Redo, Key::Y, &[Key::Control])
                 .push(KeyComboAction::Save, Key::S, &[Key::Control])
+                .push(
+                    KeyComboAction::ImportDithered,
+                    Key::I,
+                    &[Key::Control, Key::Alt],
+                )
+                .push(KeyComboAction::Import, Key::I, &[Key::Control])
                 .push(KeyComboAction::FlipVertically, Key::V, &[])
                 .push(KeyComboAction::FlipHorizontally, Key::F, &[])
+                .push(KeyComboAction::Rotate, Key::R, &[])
+                .push(KeyComboAction::Outline, Key::O, &[])
+                .push(KeyComboAction::ScaleUp, Key::UpArrow, &[Key::Alt])
+                .push(KeyComboAction::ScaleDown, Key::DownArrow, &[Key::Alt])
                 .push(KeyComboAction::PreviousTab, Key::LeftArrow, &[Key::Alt])
-                .push(KeyComboAction::NextTab, Key::RightArrow, &[Key::Alt]),
-            clipboard: Clipboard::new(),
+                .push(KeyComboAction::NextTab, Key::RightArrow, &[Key::Alt])
+                // A combo, so that copying (Ctrl+C) doesn't also switch modes.
+                .push(KeyComboAction::SwitchMapMode, Key::C, &[])
+                // Combos too, so that Ctrl+S (or Alt+S) doesn't also shift the sprite.
+                .push(KeyComboAction::Shift(ShiftDirection::Up), Key::W, &[])
+                .push(KeyComboAction::Shift(ShiftDirection::Right), Key::D, &[])
+                .push(KeyComboAction::Shift(ShiftDirection::Down), Key::S, &[])
+                .push(KeyComboAction::Shift(ShiftDirection::Left), Key::A, &[]),
             commands: Commands::new(),
             editor_sprites: load_editor_sprite_sheet()
                 // TODO: Change this to actually crash if it failed.
@@ -258,44 +709,76 @@ impl ElmApp for Editor {
             sprite_editor: sprite::Editor::new(),
             brush_size: BrushSize::tiny(),
             selected_sprite: 0,
+            selected_sprites_size: (1, 1),
+            selecting_sprites_from: None,
+            dirty: Dirty::default(),
+            edited_since_save_state: Dirty::default(),
+            prompt: None,
+            quitting: false,
+            backups: None,
         }
     }
 
     fn update(&mut self, msg: &Msg, resources: &mut Resources) {
         match msg {
             &Msg::SpriteEditorMsg(sprite_msg) => {
-                self.sprite_editor.update(sprite_msg);
+                let edits =
+                    self.sprite_editor
+                        .update(sprite_msg, &resources.sprite_sheet, self.brush_size);
+                self.apply_sprite_edits(&edits, &mut resources.sprite_sheet);
+
+                match sprite_msg {
+                    sprite::Msg::ToolSelected(_) => {
+                        self.bottom_bar_text =
+                            self.sprite_editor.tool().to_human_readable().to_owned();
+                    }
+                    sprite::Msg::Zoom { .. } => {
+                        self.select_canvas_sprites();
+                        self.bottom_bar_text =
+                            format!("ZOOM:{}", self.sprite_editor.zoom().to_human_readable());
+                    }
+                    _ => {}
+                }
             }
             &Msg::MapEditorMsg(map_msg) => {
-                self.map_editor.update(map_msg);
+                let edits = self
+                    .map_editor
+                    .update(map_msg, &resources.map, &self.map_brush());
+                self.apply_map_edits(&edits, &mut resources.map);
+
+                let zoom = self.map_editor.zoom().to_human_readable();
+                self.bottom_bar_text = match (map_msg, self.map_editor.hovered_cell()) {
+                    (map::Msg::ToolSelected(tool), _) => tool.to_human_readable().to_owned(),
+                    (_, Some((x, y))) => format!("X:{x} Y:{y} ZOOM:{zoom}"),
+                    (_, None) => format!("ZOOM:{zoom}"),
+                };
             }
+            // The controller answers prompts, see `Editor::answer_prompt`.
+            &Msg::KeyboardEvent(KeyboardEvent {
+                state: KeyState::Down,
+                ..
+            }) if self.is_prompting() => {}
             &Msg::KeyboardEvent(event) => {
                 self.handle_key_combos(event, resources);
-
-                // TODO: Handle these as key combos
-                if let KeyboardEvent {
-                    key,
-                    state: KeyState::Down,
-                } = event
-                {
-                    if let Some(shift_direction) = ShiftDirection::from_key(&key) {
-                        self.shift_sprite(shift_direction, &mut resources.sprite_sheet)
-                    }
-                };
             }
             Msg::SpriteTabClicked => {
-                self.tab = Tab::SpriteEditor;
+                self.show_tab(Tab::SpriteEditor);
                 println!("Sprite button clicked");
             }
             Msg::MapButtonClicked => {
-                self.tab = Tab::MapEditor;
+                self.show_tab(Tab::MapEditor);
                 println!("Map button clicked");
             }
             Msg::SpritePageSelected(selected_sprite_page) => {
                 self.selected_sprite_page = *selected_sprite_page;
             }
-            Msg::SpriteButtonClicked(selected_sprite) => {
-                self.selected_sprite = *selected_sprite;
+            &Msg::SpriteButtonClicked(sprite) => {
+                self.select_sprites(sprite);
+            }
+            Msg::MouseReleased => {
+                self.selecting_sprites_from = None;
+                // Everything drawn since the mouse was pressed is undone at once.
+                self.commands.close_group();
             }
             Msg::FlagHovered { bit_number } => {
                 self.bottom_bar_text = format!("FLAG {} (0X{:X})", bit_number, 1 << bit_number);
@@ -309,39 +792,21 @@ impl ElmApp for Editor {
                 resources
                     .sprite_flags
                     .fset(self.selected_sprite, flag_index, !flag_value);
-            }
-            &Msg::SpriteEdited { x, y, color } => {
-                let sprite = resources.sprite_sheet.get_sprite_mut(self.selected_sprite);
-                let x = x as isize;
-                let y = y as isize;
-                let previous_color = sprite.pget(x, y);
-
-                self.commands.push(Command::pixel_changed(
+                self.commands.push(Command::flag_changed(
                     self.selected_sprite,
-                    x,
-                    y,
-                    previous_color,
-                    color,
+                    flag_index,
+                    flag_value,
+                    !flag_value,
                 ));
-
-                for (x, y) in self
-                    .brush_size
-                    .iter()
-                    .map(|(local_x, local_y)| (local_x + x, local_y + y))
-                {
-                    sprite.pset(x, y, color);
-                }
-            }
-            &Msg::ToolSelected(selected_tool) => {
-                self.selected_tool = selected_tool;
+                self.mark_edited(Dirty {
+                    flags: true,
+                    ..Dirty::default()
+                });
             }
             &Msg::ColorHovered(color) => {
                 self.bottom_bar_text = format!("COLOUR {color}");
             }
 
-            &Msg::ClickedMapTile { x, y } => {
-                resources.map.mset(x, y, self.selected_sprite as u8);
-            }
             &Msg::BrushSizeSelected(brush_size) => {
                 self.brush_size = brush_size;
                 self.bottom_bar_text =
@@ -357,49 +822,73 @@ impl ElmApp for Editor {
     fn view(&mut self, resources: &Resources) -> Element<'_, Msg> {
         const BACKGROUND: u8 = 5;
 
+        let sprite_tools = self.sprite_editor.tools();
+        let selected_sprite_tool = sprite_tools
+            .iter()
+            .position(|&tool| tool == self.sprite_editor.tool())
+            .unwrap_or(0);
+        let selected_map_tool = map::Tool::ALL
+            .iter()
+            .position(|&tool| tool == self.map_editor.tool())
+            .unwrap_or(0);
+
         Tree::new()
             .push(DrawFn::new(|draw| {
                 draw.rectfill(0, 0, 127, 127, BACKGROUND)
             }))
-            .push(top_bar(
-                &mut self.sprite_button_state,
-                &mut self.map_button_state,
-                self.tab,
-            ))
             .push(match self.tab {
                 Tab::SpriteEditor => {
                     let selected_sprite_flags =
                         resources.sprite_flags.get(self.selected_sprite).unwrap();
-                    let selected_sprite = resources.sprite_sheet.get_sprite(self.selected_sprite);
 
                     self.sprite_editor.view(
                         selected_sprite_flags,
-                        selected_sprite,
+                        &resources.sprite_sheet,
                         &self.editor_sprites,
                         self.brush_size,
                         &Msg::SpriteEditorMsg,
                     )
                 }
-                Tab::MapEditor => Tree::new()
-                    .push(self.map_editor.view(
-                        &resources.map,
-                        0,
-                        8,
-                        &|x, y| Msg::ClickedMapTile { x, y },
-                        &Msg::MapEditorMsg,
-                    ))
-                    .into(),
+                Tab::MapEditor => {
+                    let brush = self.map_brush();
+
+                    self.map_editor
+                        .view(&resources.map, &brush, Msg::MapEditorMsg)
+                }
             })
+            // Drawn after the map, which can be scrolled under it.
+            .push(top_bar(
+                &mut self.sprite_button_state,
+                &mut self.map_button_state,
+                self.tab,
+            ))
             .push(tools_row(
                 76,
                 self.selected_sprite,
                 self.selected_sprite_page,
                 &mut self.tab_buttons,
-                self.selected_tool,
-                &mut self.tool_buttons,
+                match self.tab {
+                    Tab::SpriteEditor => tool_buttons(
+                        76,
+                        &sprite_tools.map(sprite::Tool::icon),
+                        selected_sprite_tool,
+                        &mut self.tool_buttons,
+                        move |index| {
+                            Msg::SpriteEditorMsg(sprite::Msg::ToolSelected(sprite_tools[index]))
+                        },
+                    ),
+                    Tab::MapEditor => tool_buttons(
+                        76,
+                        &map::Tool::ALL.map(map::Tool::icon),
+                        selected_map_tool,
+                        &mut self.map_tool_buttons,
+                        |index| Msg::MapEditorMsg(map::Msg::ToolSelected(map::Tool::ALL[index])),
+                    ),
+                },
             ))
             .push(sprite_view(
                 self.selected_sprite,
+                self.selected_sprites_size,
                 self.selected_sprite_page,
                 &mut self.sprite_buttons,
                 87,
@@ -413,16 +902,19 @@ impl ElmApp for Editor {
     fn subscriptions(&self, event: &Event) -> Vec<Msg> {
         match event {
             Event::Input(InputEvent::Keyboard(event)) => Some(Msg::KeyboardEvent(*event)),
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state: KeyState::Up,
+            })) => Some(Msg::MouseReleased),
             Event::Tick { .. } => None,
-            Event::WindowClosed => todo!("WindowClosed event not yet handled"),
+            // Handled by the controller, which quits even when the editor isn't shown.
+            Event::WindowClosed => None,
             _ => None,
         }
         .into_iter()
         .chain(match self.tab {
-            Tab::MapEditor => map::Editor::subscriptions(event)
-                .map(Msg::MapEditorMsg)
-                .into_iter(),
-            _ => None.into_iter(),
+            Tab::MapEditor => map::Editor::subscriptions(event).map(Msg::MapEditorMsg),
+            Tab::SpriteEditor => sprite::Editor::subscriptions(event).map(Msg::SpriteEditorMsg),
         })
         .collect()
     }
@@ -485,8 +977,7 @@ fn tools_row<'a>(
     sprite: usize,
     selected_tab: usize,
     tab_buttons: &'a mut [button::State],
-    selected_tool: usize,
-    tool_buttons: &'a mut [button::State],
+    tool_buttons: Vec<Element<'a, Msg>>,
 ) -> Element<'a, Msg> {
     let mut children = vec![DrawFn::new(move |draw| {
         const HEIGHT: i32 = 11;
@@ -494,33 +985,7 @@ fn tools_row<'a>(
     })
     .into()];
 
-    const TOOLS: &[usize] = &[15, 31];
-
-    for (tool_index, tool_button) in tool_buttons.iter_mut().enumerate() {
-        let spr = TOOLS[tool_index];
-
-        let x = (9 + 8 * tool_index) as i32;
-        let y = y + 2;
-        children.push(
-            Button::new(
-                x,
-                y,
-                8,
-                8,
-                Some(Msg::ToolSelected(tool_index)),
-                tool_button,
-                DrawFn::new(move |pico8| {
-                    pico8.palt(Some(0));
-                    if selected_tool == tool_index {
-                        pico8.pal(13, 7);
-                    }
-                    pico8.editor_spr(spr, 0, 0);
-                    pico8.pal(13, 13);
-                }),
-            )
-            .into(),
-        );
-    }
+    children.extend(tool_buttons);
 
     for (sprite_tab, tab_button_state) in tab_buttons.iter_mut().enumerate() {
         let base_sprite = if selected_tab == sprite_tab { 33 } else { 17 };
@@ -564,9 +1029,47 @@ fn tools_row<'a>(
     Tree::with_children(children).into()
 }
 
+/// Buttons for the tools of the current tab, with editor sprites as `icons`.
+fn tool_buttons<'a>(
+    y: i32,
+    icons: &[usize],
+    selected_tool: usize,
+    tool_buttons: &'a mut [button::State],
+    on_select: impl Fn(usize) -> Msg,
+) -> Vec<Element<'a, Msg>> {
+    tool_buttons
+        .iter_mut()
+        .zip(icons.iter().copied())
+        .enumerate()
+        .map(|(tool_index, (tool_button, spr))| {
+            let x = (2 + 8 * tool_index) as i32;
+            let y = y + 2;
+
+            Button::new(
+                x,
+                y,
+                8,
+                8,
+                Some(on_select(tool_index)),
+                tool_button,
+                DrawFn::new(move |pico8| {
+                    pico8.palt(Some(0));
+                    if selected_tool == tool_index {
+                        pico8.pal(13, 7);
+                    }
+                    pico8.editor_spr(spr, 0, 0);
+                    pico8.pal(13, 13);
+                }),
+            )
+            .into()
+        })
+        .collect()
+}
+
 /// The 4 rows of sprites at the bottom of the sprite editor
 fn sprite_view(
     selected_sprite: usize,
+    (selected_width, selected_height): (usize, usize),
     selected_tab: usize,
     sprite_buttons: &mut [button::State],
     y: i32,
@@ -607,16 +1110,25 @@ fn sprite_view(
         );
     }
 
-    // Draw selected sprite highlight
+    // Draw selected sprite highlight, clipped to the rows of the page.
     {
-        // TODO: Fix (wrong highlight when switching pages)
-        let (x, y) = sprite_position(selected_sprite);
-        children.push(
-            DrawFn::new(move |draw| {
-                draw.rect(x - 1, y - 1, x + 8, y + 8, 7);
-            })
-            .into(),
-        )
+        let page_row = |sprite: usize| (sprite / 16) as i32 - selected_tab as i32 * 4;
+        let top = page_row(selected_sprite);
+        let bottom = top + selected_height as i32;
+
+        if top < 4 && bottom > 0 {
+            let x = (selected_sprite % 16) as i32 * 8;
+            let width = selected_width as i32 * 8;
+            let top = y + 1 + top.max(0) * 8;
+            let bottom = y + 1 + bottom.min(4) * 8;
+
+            children.push(
+                DrawFn::new(move |draw| {
+                    draw.rect(x - 1, top - 1, x + width, bottom, 7);
+                })
+                .into(),
+            )
+        }
     }
 
     Tree::with_children(children).into()
@@ -645,23 +1157,372 @@ enum ShiftDirection {
 }
 
 impl ShiftDirection {
-    fn from_key(key: &Key) -> Option<Self> {
-        use ShiftDirection::*;
-
-        match key {
-            Key::W => Some(Up),
-            Key::D => Some(Right),
-            Key::S => Some(Down),
-            Key::A => Some(Left),
-            _ => None,
+    fn offset(self) -> Vec2i {
+        match self {
+            ShiftDirection::Up => vec2(0, -1),
+            ShiftDirection::Down => vec2(0, 1),
+            ShiftDirection::Left => vec2(-1, 0),
+            ShiftDirection::Right => vec2(1, 0),
         }
     }
-    fn shift(&self, sprite: &mut Sprite) {
-        match self {
-            ShiftDirection::Up => sprite.shift_up(),
-            ShiftDirection::Down => sprite.shift_down(),
-            ShiftDirection::Left => sprite.shift_left(),
-            ShiftDirection::Right => sprite.shift_right(),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quits_right_away_without_unsaved_changes() {
+        let mut editor = <Editor as ElmApp>::init();
+
+        editor.request_quit(&Resources::empty_in_temp_dir("editor"), false);
+
+        assert!(editor.is_quitting());
+    }
+
+    #[test]
+    fn asks_before_quitting_with_unsaved_changes() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        editor.update(&Msg::MapEditorMsg(map::Msg::Press((0, 0))), &mut resources);
+
+        editor.request_quit(&resources, false);
+        assert!(editor.is_prompting());
+        assert_eq!(editor.notification.content(), "SAVE CHANGES? Y/N/ESC");
+
+        editor.answer_prompt(Key::Escape, &mut resources);
+        assert!(!editor.is_prompting());
+        assert!(!editor.is_quitting());
+
+        editor.request_quit(&resources, false);
+        editor.answer_prompt(Key::N, &mut resources);
+        assert!(editor.is_quitting());
+    }
+
+    #[test]
+    fn saving_or_discarding_the_changes_deletes_the_backups() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.assets_path = format!("{}-backups", resources.assets_path);
+        let backups_path = format!("{}.backups", resources.assets_path);
+        std::fs::create_dir_all(&resources.assets_path).unwrap();
+        editor.enable_backups(&resources);
+
+        // Each answer after editing a different tile, so that there's something to save.
+        for (x, answer) in [(0, Key::Y), (1, Key::N)] {
+            editor.update(&Msg::MapEditorMsg(map::Msg::Press((x, 0))), &mut resources);
+            editor.backups.as_mut().unwrap().backup(&resources).unwrap();
+            assert!(Path::new(&backups_path).exists());
+
+            editor.request_quit(&resources, false);
+            editor.answer_prompt(answer, &mut resources);
+            assert!(!Path::new(&backups_path).exists());
+        }
+
+        std::fs::remove_dir_all(&resources.assets_path).unwrap();
+    }
+
+    #[test]
+    fn failing_to_save_keeps_the_changes_and_the_backups() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        // A file instead of a directory, so that nothing can be written into it.
+        resources.assets_path = format!("{}-not-a-directory", resources.assets_path);
+        let backups_path = format!("{}.backups", resources.assets_path);
+        std::fs::write(&resources.assets_path, "").unwrap();
+        editor.enable_backups(&resources);
+
+        editor.update(&Msg::MapEditorMsg(map::Msg::Press((0, 0))), &mut resources);
+        editor.backups.as_mut().unwrap().backup(&resources).unwrap();
+
+        editor.request_quit(&resources, false);
+        editor.answer_prompt(Key::Y, &mut resources);
+        assert_eq!(editor.notification.content(), "COULDN'T SAVE");
+        assert!(!editor.is_quitting());
+        assert!(editor.has_unsaved_changes());
+        assert!(Path::new(&backups_path).exists());
+
+        std::fs::remove_file(&resources.assets_path).unwrap();
+        std::fs::remove_dir_all(&backups_path).unwrap();
+    }
+
+    // Holds the keys down in order, then releases them.
+    fn press(editor: &mut Editor, resources: &mut Resources, keys: &[Key]) {
+        for (&key, state) in keys
+            .iter()
+            .zip(std::iter::repeat(KeyState::Down))
+            .chain(keys.iter().rev().zip(std::iter::repeat(KeyState::Up)))
+        {
+            editor.update(&Msg::KeyboardEvent(KeyboardEvent { key, state }), resources);
+        }
+    }
+
+    #[test]
+    fn undo_works_on_every_tab() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        editor.update(&Msg::FlagToggled(3), &mut resources);
+        editor.update(&Msg::MapButtonClicked, &mut resources);
+        editor.update(&Msg::SpriteButtonClicked(4), &mut resources);
+        editor.update(&Msg::MapEditorMsg(map::Msg::Press((5, 5))), &mut resources);
+        assert_eq!(resources.map.mget(5, 5), 4);
+
+        press(&mut editor, &mut resources, &[Key::Control, Key::Z]);
+        assert_eq!(resources.map.mget(5, 5), 0);
+        press(&mut editor, &mut resources, &[Key::Control, Key::Z]);
+        assert!(!resources.sprite_flags.fget_n(0, 3));
+
+        press(&mut editor, &mut resources, &[Key::Control, Key::Y]);
+        assert!(resources.sprite_flags.fget_n(0, 3));
+    }
+
+    #[test]
+    fn strokes_are_undone_at_once() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        let pixels = |resources: &Resources| {
+            let sprite = resources.sprite_sheet.get_sprite(0);
+
+            [sprite.pget(1, 1), sprite.pget(2, 1)]
+        };
+
+        // Sends the messages to the sprite editor, then releases the mouse.
+        fn stroke(editor: &mut Editor, resources: &mut Resources, msgs: &[sprite::Msg]) {
+            for &msg in msgs {
+                editor.update(&Msg::SpriteEditorMsg(msg), resources);
+            }
+            editor.update(&Msg::MouseReleased, resources);
         }
+
+        stroke(
+            &mut editor,
+            &mut resources,
+            &[
+                sprite::Msg::ColorSelected(8),
+                sprite::Msg::Press(vec2(1, 1)),
+                sprite::Msg::Drag(vec2(2, 1)),
+                sprite::Msg::Release,
+            ],
+        );
+        stroke(
+            &mut editor,
+            &mut resources,
+            &[
+                sprite::Msg::ColorSelected(9),
+                sprite::Msg::Press(vec2(1, 1)),
+                sprite::Msg::Release,
+            ],
+        );
+        assert_eq!(pixels(&resources), [9, 8]);
+
+        editor.handle_key_combo(KeyComboAction::Undo, &mut resources);
+        assert_eq!(pixels(&resources), [8, 8]);
+        editor.handle_key_combo(KeyComboAction::Undo, &mut resources);
+        assert_eq!(pixels(&resources), [0, 0]);
+    }
+
+    #[test]
+    fn zoomed_out_canvases_edit_blocks_of_sprites() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.sprite_sheet.set(14 * 8, 0, 8);
+
+        editor.update(&Msg::SpriteButtonClicked(15), &mut resources);
+        editor.update(
+            &Msg::SpriteEditorMsg(sprite::Msg::Zoom { zoom_in: false }),
+            &mut resources,
+        );
+        assert_eq!(editor.selected_sprite, 14);
+        assert_eq!(editor.selected_sprites_size, (2, 2));
+
+        // Flips the whole 16x16 block, as a single undoable change.
+        editor.handle_key_combo(KeyComboAction::FlipHorizontally, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(14 * 8, 0), 0);
+        assert_eq!(resources.sprite_sheet.get(16 * 8 - 1, 0), 8);
+
+        editor.handle_key_combo(KeyComboAction::Undo, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(14 * 8, 0), 8);
+        assert_eq!(resources.sprite_sheet.get(16 * 8 - 1, 0), 0);
+    }
+
+    #[test]
+    fn transforms_are_undone_at_once() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.sprite_sheet.set(0, 0, 8);
+        editor.update(
+            &Msg::SpriteEditorMsg(sprite::Msg::ColorSelected(1)),
+            &mut resources,
+        );
+
+        editor.handle_key_combo(KeyComboAction::Rotate, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(7, 0), 8);
+        editor.handle_key_combo(KeyComboAction::Outline, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(6, 0), 1);
+        assert_eq!(resources.sprite_sheet.get(7, 1), 1);
+
+        editor.handle_key_combo(KeyComboAction::Undo, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(6, 0), 0);
+        assert_eq!(resources.sprite_sheet.get(7, 1), 0);
+        editor.handle_key_combo(KeyComboAction::Undo, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(0, 0), 8);
+        assert_eq!(resources.sprite_sheet.get(7, 0), 0);
+    }
+
+    #[test]
+    fn combos_with_other_modifiers_dont_transform_sprites() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.sprite_sheet.set(0, 0, 8);
+
+        // Ctrl+R restarts the game, see `Controller`.
+        press(&mut editor, &mut resources, &[Key::Control, Key::R]);
+        press(&mut editor, &mut resources, &[Key::Alt, Key::O]);
+
+        assert_eq!(resources.sprite_sheet.get(0, 0), 8);
+        assert_eq!(resources.sprite_sheet.get(7, 0), 0);
+        assert!(!editor.has_unsaved_changes());
+
+        press(&mut editor, &mut resources, &[Key::R]);
+        assert_eq!(resources.sprite_sheet.get(7, 0), 8);
+    }
+
+    #[test]
+    fn saving_doesnt_shift_the_sprite() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.sprite_sheet.set(0, 0, 8);
+
+        // Alt+S saves the game's state, see `Controller`.
+        press(&mut editor, &mut resources, &[Key::Alt, Key::S]);
+        assert_eq!(resources.sprite_sheet.get(0, 0), 8);
+        assert!(!editor.has_unsaved_changes());
+
+        press(&mut editor, &mut resources, &[Key::S]);
+        assert_eq!(resources.sprite_sheet.get(0, 1), 8);
+    }
+
+    #[test]
+    fn copying_doesnt_switch_map_modes() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        editor.update(&Msg::MapButtonClicked, &mut resources);
+        let shows_sprites = editor.map_editor.shows_sprites();
+
+        press(&mut editor, &mut resources, &[Key::Control, Key::C]);
+        assert_eq!(editor.map_editor.shows_sprites(), shows_sprites);
+
+        press(&mut editor, &mut resources, &[Key::C]);
+        assert_ne!(editor.map_editor.shows_sprites(), shows_sprites);
+    }
+
+    #[test]
+    fn sprite_transforms_do_nothing_in_the_map_editor() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.sprite_sheet.set(0, 0, 8);
+        editor.tab = Tab::MapEditor;
+
+        for key_combo in [
+            KeyComboAction::Rotate,
+            KeyComboAction::Outline,
+            KeyComboAction::ScaleUp,
+            KeyComboAction::ScaleDown,
+        ] {
+            editor.handle_key_combo(key_combo, &mut resources);
+        }
+
+        assert_eq!(resources.sprite_sheet.get(0, 0), 8);
+        assert_eq!(resources.sprite_sheet.get(7, 0), 0);
+        assert_eq!(resources.sprite_sheet.get(1, 0), 0);
+        assert!(!editor.has_unsaved_changes());
+    }
+
+    #[test]
+    fn sprites_are_copied_and_pasted_in_pico8_format() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.sprite_sheet.set(1, 0, 10);
+
+        editor.handle_key_combo(KeyComboAction::Copy, &mut resources);
+        let copied = editor.clipboard.get_text().unwrap();
+        assert!(copied.starts_with("[gfx]08080a000000"));
+
+        editor
+            .clipboard
+            .set_text("[gfx]02017c[/gfx]".to_owned())
+            .unwrap();
+        editor.handle_key_combo(KeyComboAction::Paste, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(0, 0), 7);
+        assert_eq!(resources.sprite_sheet.get(1, 0), 12);
+
+        editor.handle_key_combo(KeyComboAction::Undo, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(1, 0), 10);
+    }
+
+    #[test]
+    fn png_images_are_imported_at_once() {
+        let mut editor = <Editor as ElmApp>::init();
+        let mut resources = Resources::empty_in_temp_dir("editor");
+        resources.assets_path = format!("{}-import", resources.assets_path);
+        std::fs::create_dir_all(&resources.assets_path).unwrap();
+
+        editor.handle_key_combo(KeyComboAction::Import, &mut resources);
+        assert!(!editor.has_unsaved_changes());
+
+        // Red, then almost dark blue.
+        let mut image = vec![];
+        let mut encoder = png::Encoder::new(&mut image, 2, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[0xFF, 0x00, 0x4D, 0x20, 0x28, 0x50])
+            .unwrap();
+        let path = Path::new(&resources.assets_path).join(IMPORTED_IMAGE);
+        std::fs::write(&path, image).unwrap();
+
+        editor.handle_key_combo(KeyComboAction::Import, &mut resources);
+        std::fs::remove_dir_all(&resources.assets_path).unwrap();
+        assert_eq!(resources.sprite_sheet.get(0, 0), 8);
+        assert_eq!(resources.sprite_sheet.get(1, 0), 1);
+
+        editor.handle_key_combo(KeyComboAction::Undo, &mut resources);
+        assert_eq!(resources.sprite_sheet.get(0, 0), 0);
+        assert_eq!(resources.sprite_sheet.get(1, 0), 0);
+    }
+
+    #[test]
+    fn only_changed_files_are_saved() {
+        let resources = Resources::empty_in_temp_dir("editor");
+        let file_names = |dirty| -> Vec<String> {
+            changed_files(&resources, dirty)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()
+        };
+
+        assert!(file_names(Dirty::default()).is_empty());
+        assert_eq!(
+            file_names(Dirty {
+                flags: true,
+                ..Dirty::default()
+            }),
+            ["sprite_flags.txt"]
+        );
+        assert_eq!(
+            file_names(Dirty {
+                map: true,
+                ..Dirty::default()
+            }),
+            ["map.txt", "map.ppm"]
+        );
+        assert_eq!(
+            file_names(Dirty {
+                sprite_sheet: true,
+                ..Dirty::default()
+            }),
+            ["sprite_sheet.txt", "sprite_sheet.ppm", "map.ppm"]
+        );
     }
 }
@@ -0,0 +1,216 @@
+use runty8_core::serialize::Serialize;
+use runty8_core::{Flags, Map, Resources, SpriteSheet};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often resources with unsaved changes are backed up.
+const BACKUP_INTERVAL_MILLIS: f64 = 60_000.0;
+/// How many backups are kept, older ones get deleted.
+const MAX_BACKUPS: usize = 10;
+
+/// Periodic copies of the resources, so that unsaved edits survive a crash.
+///
+/// Each backup is a directory named after the time it was made (in milliseconds since the epoch),
+/// containing the same text files the editor saves.
+#[derive(Debug)]
+pub(crate) struct Backups {
+    directory: PathBuf,
+    millis_since_backup: f64,
+    // What was backed up last, to avoid making identical backups.
+    last_backup: Option<Vec<(String, String)>>,
+}
+
+/// A backup that can be restored, see [`Backups::newer_than_assets`].
+#[derive(Debug)]
+pub(crate) struct Backup {
+    directory: PathBuf,
+}
+
+impl Backups {
+    pub(crate) fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            millis_since_backup: 0.0,
+            last_backup: None,
+        }
+    }
+
+    /// Backups in `<assets_path>.backups`, next to the assets directory.
+    pub(crate) fn next_to(assets_path: &str) -> Self {
+        Self::new(format!("{}.backups", assets_path.trim_end_matches('/')))
+    }
+
+    /// Backs `resources` up if enough time passed since the last backup.
+    pub(crate) fn tick(&mut self, delta_millis: f64, resources: &Resources) -> Result<(), String> {
+        self.millis_since_backup += delta_millis;
+
+        if self.millis_since_backup >= BACKUP_INTERVAL_MILLIS {
+            self.millis_since_backup = 0.0;
+
+            return self.backup(resources);
+        }
+
+        Ok(())
+    }
+
+    /// Backs `resources` up right away, deleting the oldest backups if there are too many.
+    pub(crate) fn backup(&mut self, resources: &Resources) -> Result<(), String> {
+        let files = files(resources);
+        if self.last_backup.as_ref() == Some(&files) {
+            return Ok(());
+        }
+
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let directory = self.directory.join(millis.to_string());
+
+        std::fs::create_dir_all(&directory)
+            .and_then(|_| {
+                files
+                    .iter()
+                    .try_for_each(|(name, contents)| std::fs::write(directory.join(name), contents))
+            })
+            .map_err(|err| format!("[Backup] Couldn't write {}: {err}", directory.display()))?;
+        self.last_backup = Some(files);
+
+        self.rotate()
+    }
+
+    /// Deletes every backup, once the changes in them were either saved or discarded.
+    pub(crate) fn clear(&mut self) -> Result<(), String> {
+        self.millis_since_backup = 0.0;
+        self.last_backup = None;
+
+        match std::fs::remove_dir_all(&self.directory) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(format!(
+                "[Backup] Couldn't delete {}: {err}",
+                self.directory.display()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn rotate(&self) -> Result<(), String> {
+        let backups = self.list();
+        let excess = backups.len().saturating_sub(MAX_BACKUPS);
+
+        for (_, directory) in backups.into_iter().take(excess) {
+            std::fs::remove_dir_all(&directory).map_err(|err| {
+                format!("[Backup] Couldn't delete {}: {err}", directory.display())
+            })?;
+        }
+
+        Ok(())
+    }
+
+    // Backups, from oldest to newest, along with when they were made.
+    fn list(&self) -> Vec<(SystemTime, PathBuf)> {
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+
+        let mut backups: Vec<_> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let millis = path.file_name()?.to_str()?.parse().ok()?;
+
+                Some((UNIX_EPOCH + Duration::from_millis(millis), path))
+            })
+            .collect();
+        backups.sort();
+
+        backups
+    }
+
+    /// The newest backup, if it was made after the assets in `assets_path` were last saved.
+    pub(crate) fn newer_than_assets(&self, assets_path: &str) -> Option<Backup> {
+        let (backup_time, directory) = self.list().pop()?;
+
+        let assets_time = [
+            Flags::file_name(),
+            SpriteSheet::file_name(),
+            Map::file_name(),
+        ]
+        .iter()
+        .filter_map(|name| {
+            std::fs::metadata(Path::new(assets_path).join(name))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .max();
+
+        match assets_time {
+            Some(assets_time) if assets_time >= backup_time => None,
+            _ => Some(Backup { directory }),
+        }
+    }
+}
+
+impl Backup {
+    /// Replaces `resources` (except for the assets path) with the backed up ones.
+    pub(crate) fn restore(&self, resources: &mut Resources) -> Result<(), String> {
+        let read = |name: String| {
+            let path = self.directory.join(name);
+
+            std::fs::read_to_string(&path)
+                .map_err(|err| format!("[Backup] Couldn't read {}: {err}", path.display()))
+        };
+
+        let sprite_flags = Flags::deserialize(&read(Flags::file_name())?)?;
+        let sprite_sheet = SpriteSheet::deserialize(&read(SpriteSheet::file_name())?)?;
+        let map = Map::deserialize(&read(Map::file_name())?)?;
+
+        resources.sprite_flags = sprite_flags;
+        resources.sprite_sheet = sprite_sheet;
+        resources.map = map;
+
+        Ok(())
+    }
+}
+
+// The files in a backup, and their contents.
+fn files(resources: &Resources) -> Vec<(String, String)> {
+    vec![
+        (Flags::file_name(), resources.sprite_flags.serialize()),
+        (SpriteSheet::file_name(), resources.sprite_sheet.serialize()),
+        (Map::file_name(), resources.map.serialize()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_can_be_restored_and_rotate() {
+        let mut resources = Resources::empty_in_temp_dir("backups");
+        let mut backups = Backups::next_to(&resources.assets_path);
+
+        for sprite in 0..MAX_BACKUPS + 2 {
+            resources.map.mset(0, 0, sprite as u8);
+            backups.backup(&resources).unwrap();
+            // Identical backups are skipped.
+            backups.backup(&resources).unwrap();
+            // Backups are named after the millisecond they were made in.
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(backups.list().len(), MAX_BACKUPS);
+
+        let mut restored = Resources::empty(resources.assets_path.clone());
+        backups
+            .newer_than_assets(&resources.assets_path)
+            .unwrap()
+            .restore(&mut restored)
+            .unwrap();
+        backups.clear().unwrap();
+
+        assert_eq!(restored.map.mget(0, 0), MAX_BACKUPS as u8 + 1);
+        assert!(!backups.directory.exists());
+        // Clearing twice is fine.
+        backups.clear().unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+/// The system clipboard, so that sprites and map tiles can be copied between runty8 and pico8
+/// (or anything else that handles text).
+pub(crate) struct Clipboard {
+    system: Option<arboard::Clipboard>,
+    // Used when there's no system clipboard (in tests, or without a display server).
+    text: String,
+}
+
+impl std::fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Clipboard")
+            .field("system", &self.system.is_some())
+            .field("text", &self.text)
+            .finish()
+    }
+}
+
+impl Clipboard {
+    /// The system clipboard, if there's one (tests never touch it).
+    pub(crate) fn new() -> Self {
+        Self {
+            system: if cfg!(test) {
+                None
+            } else {
+                arboard::Clipboard::new().ok()
+            },
+            text: String::new(),
+        }
+    }
+
+    pub(crate) fn set_text(&mut self, text: String) -> Result<(), String> {
+        self.text = text.clone();
+
+        match &mut self.system {
+            Some(system) => system
+                .set_text(text)
+                .map_err(|error| format!("[Clipboard] Couldn't copy: {error}")),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn get_text(&mut self) -> Result<String, String> {
+        match &mut self.system {
+            Some(system) => system
+                .get_text()
+                .map_err(|error| format!("[Clipboard] Couldn't paste: {error}")),
+            None => Ok(self.text.clone()),
+        }
+    }
+}
+
+/// Writes a rectangle of values in pico8's clipboard format: `[tag]`, the width and height
+/// (2 hex digits each), every value (row by row, `digits` hex digits each), and `[/tag]`.
+///
+/// Sprites are copied as `[gfx]`, with a digit per pixel, and map tiles as `[map]`,
+/// with 2 digits per tile.
+pub(crate) fn encode(
+    tag: &str,
+    width: usize,
+    height: usize,
+    values: impl Iterator<Item = u8>,
+    digits: usize,
+) -> String {
+    let values: String = values.map(|value| format!("{value:0digits$x}")).collect();
+
+    format!("[{tag}]{width:02x}{height:02x}{values}[/{tag}]")
+}
+
+/// Reads a rectangle of values written by [`encode`], returning its width, height and values.
+pub(crate) fn decode(
+    tag: &str,
+    text: &str,
+    digits: usize,
+) -> Result<(usize, usize, Vec<u8>), String> {
+    let content = text
+        .trim()
+        .strip_prefix(&format!("[{tag}]"))
+        .and_then(|text| text.strip_suffix(&format!("[/{tag}]")))
+        .ok_or_else(|| format!("[Clipboard] Expected [{tag}] data"))?;
+    let hex = |digits: &str| {
+        u8::from_str_radix(digits, 16)
+            .map_err(|_| format!("[Clipboard] Invalid hex number: {digits}"))
+    };
+    let digits_at = |start: usize, count: usize| {
+        content
+            .get(start..start + count)
+            .ok_or_else(|| format!("[Clipboard] [{tag}] data is too short"))
+    };
+
+    let width = hex(digits_at(0, 2)?)? as usize;
+    let height = hex(digits_at(2, 2)?)? as usize;
+    if width == 0 || height == 0 {
+        return Err(format!("[Clipboard] [{tag}] data is empty"));
+    }
+    let values_length = width * height * digits;
+    if content.len() != 4 + values_length {
+        return Err(format!(
+            "[Clipboard] Expected {} digits for {}x{} [{}] data, got {}",
+            values_length,
+            width,
+            height,
+            tag,
+            content.len() - 4
+        ));
+    }
+
+    let values = (0..width * height)
+        .map(|index| hex(digits_at(4 + index * digits, digits)?))
+        .collect::<Result<_, _>>()?;
+
+    Ok((width, height, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_written_in_pico8_format() {
+        let text = encode("gfx", 2, 1, [7, 12].into_iter(), 1);
+        assert_eq!(text, "[gfx]02017c[/gfx]");
+        assert_eq!(decode("gfx", &text, 1), Ok((2, 1, vec![7, 12])));
+
+        let text = encode("map", 1, 2, [1, 255].into_iter(), 2);
+        assert_eq!(text, "[map]010201ff[/map]");
+        assert_eq!(decode("map", &text, 2), Ok((1, 2, vec![1, 255])));
+    }
+
+    #[test]
+    fn invalid_data_is_rejected() {
+        assert!(decode("gfx", "[map]010101[/map]", 1).is_err());
+        assert!(decode("gfx", "[gfx]0202123[/gfx]", 1).is_err());
+        assert!(decode("gfx", "[gfx]0101g[/gfx]", 1).is_err());
+        assert!(decode("gfx", "[gfx]0[/gfx]", 1).is_err());
+        assert!(decode("gfx", "[gfx]0000[/gfx]", 1).is_err());
+    }
+}
@@ -1,15 +1,22 @@
-use std::collections::{hash_map::Entry, HashMap};
+use std::collections::HashSet;
 
 use runty8_core::{Key, KeyState, KeyboardEvent};
 
+/// Keys that change what the other keys do.
+const MODIFIERS: [Key; 2] = [Key::Control, Key::Alt];
+
 #[derive(Debug)]
 pub struct KeyCombos<Id> {
     key_combos: Vec<KeyCombo<Id>>,
+    held_modifiers: HashSet<Key>,
 }
 
 impl<Id> KeyCombos<Id> {
     pub fn new() -> Self {
-        Self { key_combos: vec![] }
+        Self {
+            key_combos: vec![],
+            held_modifiers: HashSet::new(),
+        }
     }
 
     pub fn push(mut self, id: Id, action_key: Key, modifiers: &[Key]) -> Self {
@@ -22,18 +29,22 @@ impl<Id> KeyCombos<Id> {
 
 impl<Id: Copy> KeyCombos<Id> {
     pub fn on_event(&mut self, key_event: KeyboardEvent, mut on_combo: impl FnMut(Id)) {
-        let mut handled = false;
+        let KeyboardEvent { key, state } = key_event;
 
-        for key_combo in self.key_combos.iter_mut() {
-            match key_event.state {
-                KeyState::Up => key_combo.key_up(key_event.key),
-                KeyState::Down => {
-                    if let Some(action_id) = key_combo.key_down(key_event.key) {
-                        if !handled {
-                            handled = true;
-                            on_combo(action_id)
-                        }
-                    }
+        match state {
+            KeyState::Up => {
+                self.held_modifiers.remove(&key);
+            }
+            KeyState::Down if MODIFIERS.contains(&key) => {
+                self.held_modifiers.insert(key);
+            }
+            KeyState::Down => {
+                if let Some(key_combo) = self
+                    .key_combos
+                    .iter()
+                    .find(|key_combo| key_combo.matches(key, &self.held_modifiers))
+                {
+                    on_combo(key_combo.id)
                 }
             }
         }
@@ -43,8 +54,8 @@ impl<Id: Copy> KeyCombos<Id> {
 #[derive(Debug)]
 struct KeyCombo<Id> {
     id: Id,
-    // Must be held
-    modifiers: HashMap<Key, KeyState>,
+    // Must be held, and no other modifier can be (so that R and Ctrl+R do different things).
+    modifiers: HashSet<Key>,
     // Must be pressed
     action_key: Key,
 }
@@ -53,37 +64,12 @@ impl<Id> KeyCombo<Id> {
     fn new(id: Id, action_key: Key, modifiers: &[Key]) -> Self {
         Self {
             id,
-            modifiers: modifiers.iter().map(|key| (*key, KeyState::Up)).collect(),
+            modifiers: modifiers.iter().copied().collect(),
             action_key,
         }
     }
 
-    fn key_up(&mut self, key: Key) {
-        let entry = self.modifiers.entry(key);
-        if let Entry::Occupied(mut entry) = entry {
-            let v = entry.get_mut();
-            *v = KeyState::Up;
-        }
-    }
-
-    fn modifiers_pressed(&self) -> bool {
-        self.modifiers
-            .iter()
-            .all(|(_, state)| *state == KeyState::Down)
-    }
-}
-
-impl<Id: Copy> KeyCombo<Id> {
-    fn key_down(&mut self, key: Key) -> Option<Id> {
-        if key == self.action_key && self.modifiers_pressed() {
-            return Some(self.id);
-        }
-
-        let entry = self.modifiers.entry(key);
-        if let Entry::Occupied(mut entry) = entry {
-            let v = entry.get_mut();
-            *v = KeyState::Down;
-        }
-        None
+    fn matches(&self, key: Key, held_modifiers: &HashSet<Key>) -> bool {
+        key == self.action_key && self.modifiers == *held_modifiers
     }
 }
@@ -1,64 +1,369 @@
-use crate::ui::button::{self, Button};
-use crate::ui::{DrawFn, Element, Tree};
+mod tools;
+
+use super::sprite::line;
+use super::zoom::Zoom;
+use crate::ui::{DispatchEvent, Element, Widget};
+use crate::util::rect::Rect;
 use crate::util::vec2::{vec2, Vec2i};
-use itertools::Itertools;
-use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent, Map, MouseEvent};
+use runty8_core::{
+    Color, Event, InputEvent, Key, KeyState, KeyboardEvent, Map, MouseButton, MouseEvent, Pico8,
+    Sprite, SpriteSheet,
+};
 use std::fmt::Debug;
+pub(crate) use tools::{TileEdit, Tiles, Tool};
+
+// The part of the screen the map is shown in, between the top bar and the tools row.
+const VIEW_X: i32 = 0;
+const VIEW_Y: i32 = 8;
+const VIEW_WIDTH: i32 = 128;
+const VIEW_HEIGHT: i32 = 68;
+
+const SPRITE_SIZE: i32 = Sprite::WIDTH as i32;
+const MAP_WIDTH: i32 = Map::WIDTH_SPRITES as i32 * SPRITE_SIZE;
+const MAP_HEIGHT: i32 = Map::HEIGHT_SPRITES as i32 * SPRITE_SIZE;
+
+// The minimap shows the whole map in the bottom right corner of the view,
+// with a pixel for every 2x2 cells.
+const MINIMAP_CELLS_PER_PIXEL: i32 = 2;
+const MINIMAP_WIDTH: i32 = Map::WIDTH_SPRITES as i32 / MINIMAP_CELLS_PER_PIXEL;
+const MINIMAP_HEIGHT: i32 = Map::HEIGHT_SPRITES as i32 / MINIMAP_CELLS_PER_PIXEL;
+const MINIMAP_X: i32 = VIEW_X + VIEW_WIDTH - MINIMAP_WIDTH - 1;
+const MINIMAP_Y: i32 = VIEW_Y + VIEW_HEIGHT - MINIMAP_HEIGHT - 1;
 
 #[derive(Debug)]
 pub(crate) struct Editor {
-    buttons: Vec<button::State>,
     show_sprites_in_map: bool,
-    hovered_tile: (usize, usize),
+    show_minimap: bool,
     mouse_position: Vec2i,
-    camera: Vec2i,
+    viewport: Viewport,
     // TODO: Use a proper enum
     dragging: bool,
+    tool: Tool,
+    // The last cell the mouse went over while the left button is held.
+    pressed: Option<(usize, usize)>,
+    // Where the left button was pressed, and the last cell the mouse went over since.
+    drag: Option<(Vec2i, Vec2i)>,
+    selection: Option<Rect>,
+    // Whether the selection is being dragged around.
+    moving_selection: bool,
+    clipboard: Option<Tiles>,
+}
+
+/// Which part of the map is shown, and how big.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Viewport {
+    // Position in the map (in pixels) of the top left corner of the view.
+    camera: Vec2i,
+    zoom: Zoom,
+}
+
+impl Viewport {
+    // How many map pixels each screen pixel shows.
+    fn scale(self) -> i32 {
+        SPRITE_SIZE / self.zoom.tile_size()
+    }
+
+    /// Moves the camera, keeping the view inside the map.
+    fn with_camera(self, camera: Vec2i) -> Self {
+        let scale = self.scale();
+        let clamp = |position: i32, map_size: i32, view_size: i32| {
+            let max = (map_size - view_size * scale).max(0);
+
+            // Aligned to screen pixels, so that tiles don't change sizes.
+            position.clamp(0, max) / scale * scale
+        };
+
+        Self {
+            camera: vec2(
+                clamp(camera.x, MAP_WIDTH, VIEW_WIDTH),
+                clamp(camera.y, MAP_HEIGHT, VIEW_HEIGHT),
+            ),
+            ..self
+        }
+    }
+
+    /// Changes the zoom level, keeping the map position at `screen_position` in place.
+    fn with_zoom(self, zoom: Zoom, screen_position: Vec2i) -> Self {
+        let local = screen_position - vec2(VIEW_X, VIEW_Y);
+        let map_position = self.camera + local * self.scale();
+        let zoomed = Self { zoom, ..self };
+
+        zoomed.with_camera(map_position - local * zoomed.scale())
+    }
+
+    /// The map cell at a position on the screen, if the map is shown there.
+    fn cell_at(self, screen_position: Vec2i) -> Option<(usize, usize)> {
+        let local = screen_position - vec2(VIEW_X, VIEW_Y);
+        if local.x < 0 || local.y < 0 || local.x >= VIEW_WIDTH || local.y >= VIEW_HEIGHT {
+            return None;
+        }
+
+        let map_position = self.camera + local * self.scale();
+        if map_position.x >= MAP_WIDTH || map_position.y >= MAP_HEIGHT {
+            return None;
+        }
+
+        Some((
+            (map_position.x / SPRITE_SIZE) as usize,
+            (map_position.y / SPRITE_SIZE) as usize,
+        ))
+    }
+
+    /// Position on the screen of a cell's top left corner.
+    fn tile_position(self, col_index: usize, row_index: usize) -> Vec2i {
+        self.cell_position(vec2(col_index as i32, row_index as i32))
+    }
+
+    /// Like [`Viewport::tile_position`], also for cells outside of the map.
+    fn cell_position(self, cell: Vec2i) -> Vec2i {
+        let map_position = cell * SPRITE_SIZE;
+
+        vec2(VIEW_X, VIEW_Y)
+            + vec2(
+                (map_position.x - self.camera.x) / self.scale(),
+                (map_position.y - self.camera.y) / self.scale(),
+            )
+    }
+
+    fn first_cell(self) -> Vec2i {
+        vec2(self.camera.x / SPRITE_SIZE, self.camera.y / SPRITE_SIZE)
+    }
+
+    /// How many cells are (at least partially) visible, horizontally and vertically.
+    fn visible_cells(self) -> Vec2i {
+        let tile_size = self.zoom.tile_size();
+        let first_cell = self.first_cell();
+        let count = |view_size: i32, first: i32, map_cells: usize| {
+            ((view_size + tile_size - 1) / tile_size + 1).min(map_cells as i32 - first)
+        };
+
+        vec2(
+            count(VIEW_WIDTH, first_cell.x, Map::WIDTH_SPRITES),
+            count(VIEW_HEIGHT, first_cell.y, Map::HEIGHT_SPRITES),
+        )
+    }
 }
 
 impl Editor {
     pub(crate) fn new() -> Self {
         Self {
-            buttons: vec![button::State::new(); 144],
             show_sprites_in_map: true,
-            hovered_tile: (0, 0),
+            show_minimap: false,
             mouse_position: vec2(64, 64),
-            camera: Vec2i::zero(),
+            viewport: Viewport {
+                camera: Vec2i::zero(),
+                zoom: Zoom::Full,
+            },
             dragging: false,
+            tool: Tool::Pencil,
+            pressed: None,
+            drag: None,
+            selection: None,
+            moving_selection: false,
+            clipboard: None,
         }
     }
 
-    pub(crate) fn update(&mut self, msg: Msg) {
+    /// Handles `msg`, returning the changes it makes to the map (when painting, for example).
+    pub(crate) fn update(&mut self, msg: Msg, map: &Map, brush: &Tiles) -> Vec<TileEdit> {
         match msg {
+            Msg::ToolSelected(tool) => {
+                self.tool = tool;
+            }
+            Msg::Press((x, y)) => return self.press(vec2(x as i32, y as i32), map, brush),
+            Msg::Drag((x, y)) => return self.drag(vec2(x as i32, y as i32), brush),
+            Msg::Release => return self.release(map, brush),
             Msg::MouseMove(mouse_position) => {
                 let delta = self.mouse_position - mouse_position;
 
-                println!("[Map Editor] Mouse delta: {delta:?}");
-
                 self.mouse_position = mouse_position;
                 if self.dragging {
-                    self.camera = self.camera - delta;
+                    self.move_camera(delta * self.viewport.scale());
                 }
             }
             Msg::SetDragging(dragging) => {
                 self.dragging = dragging;
             }
+            Msg::Scroll(scroll) => {
+                self.move_camera(-scroll * self.viewport.scale());
+            }
+            Msg::Zoom {
+                zoom_in,
+                around_mouse,
+            } => {
+                let zoom = if zoom_in {
+                    self.viewport.zoom.zoom_in()
+                } else {
+                    self.viewport.zoom.zoom_out()
+                };
+                let center = if around_mouse {
+                    self.mouse_position
+                } else {
+                    vec2(VIEW_X + VIEW_WIDTH / 2, VIEW_Y + VIEW_HEIGHT / 2)
+                };
+
+                self.viewport = self.viewport.with_zoom(zoom, center);
+            }
             Msg::SwitchMapMode => {
                 self.show_sprites_in_map = !self.show_sprites_in_map;
             }
-            Msg::HoveredTile(hovered_tile) => {
-                self.hovered_tile = hovered_tile;
+            Msg::ToggleMinimap => {
+                self.show_minimap = !self.show_minimap;
+            }
+            Msg::CenterOn((x, y)) => {
+                let cell_center = vec2(x as i32, y as i32) * SPRITE_SIZE;
+                let half_view = vec2(VIEW_WIDTH, VIEW_HEIGHT) * self.viewport.scale();
+
+                self.viewport = self.viewport.with_camera(vec2(
+                    cell_center.x - half_view.x / 2,
+                    cell_center.y - half_view.y / 2,
+                ));
+            }
+        }
+
+        vec![]
+    }
+
+    fn press(&mut self, cell: Vec2i, map: &Map, brush: &Tiles) -> Vec<TileEdit> {
+        self.drag = Some((cell, cell));
+
+        match self.tool {
+            Tool::Pencil => brush.paint_at(cell),
+            Tool::Stamp => self.stamp(cell),
+            Tool::Fill => brush.flood_fill(map, cell),
+            Tool::Select => {
+                self.moving_selection =
+                    matches!(self.selection, Some(selection) if selection.contains(cell));
+
+                vec![]
+            }
+            Tool::Line | Tool::Rectangle => vec![],
+        }
+    }
+
+    fn drag(&mut self, cell: Vec2i, brush: &Tiles) -> Vec<TileEdit> {
+        let (from, last) = match self.drag {
+            Some(drag) => drag,
+            None => return vec![],
+        };
+        self.drag = Some((from, cell));
+
+        match self.tool {
+            // Every cell since the last one, so that moving the mouse quickly doesn't leave gaps.
+            Tool::Pencil => brush.paint_line(last, cell),
+            Tool::Stamp => self.stamp(cell),
+            Tool::Fill | Tool::Select | Tool::Line | Tool::Rectangle => vec![],
+        }
+    }
+
+    fn release(&mut self, map: &Map, brush: &Tiles) -> Vec<TileEdit> {
+        let (from, to) = match self.drag.take() {
+            Some(drag) => drag,
+            None => return vec![],
+        };
+
+        match (self.tool, self.selection) {
+            (Tool::Line, _) => brush.paint_line(from, to),
+            (Tool::Rectangle, _) => brush.fill_rect(Rect::from_corners(from, to)),
+            (Tool::Select, Some(selection)) if self.moving_selection => {
+                self.moving_selection = false;
+                let moved = selection.moved(to - from);
+                self.selection = Some(moved);
+
+                tools::move_tiles(map, selection, moved)
+            }
+            (Tool::Select, _) => {
+                self.selection = Some(Rect::from_corners(from, to));
+
+                vec![]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn stamp(&self, cell: Vec2i) -> Vec<TileEdit> {
+        self.clipboard
+            .as_ref()
+            .map(|tiles| tiles.paint_at(cell))
+            .unwrap_or_default()
+    }
+
+    /// Copies the selected tiles, returning them (or `None` if nothing is selected).
+    pub(crate) fn copy(&mut self, map: &Map) -> Option<Tiles> {
+        let tiles = Tiles::from_map(map, self.selection?);
+        self.clipboard = Some(tiles.clone());
+
+        Some(tiles)
+    }
+
+    /// Replaces the copied tiles (with the ones in the system clipboard, for example).
+    pub(crate) fn set_clipboard(&mut self, tiles: Tiles) {
+        self.clipboard = Some(tiles);
+    }
+
+    /// Pastes the copied tiles over the selection (or the hovered cell), and selects them.
+    pub(crate) fn paste(&mut self) -> Vec<TileEdit> {
+        let target = self
+            .selection
+            .map(Rect::top_left)
+            .or_else(|| self.hovered_cell().map(|(x, y)| vec2(x as i32, y as i32)));
+
+        match (target, &self.clipboard) {
+            (Some(target), Some(tiles)) => {
+                self.selection = Some(Rect {
+                    x: target.x,
+                    y: target.y,
+                    width: tiles.width(),
+                    height: tiles.height(),
+                });
+
+                tiles.paint_at(target)
             }
+            _ => vec![],
         }
     }
 
+    pub(crate) fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    #[cfg(test)]
+    pub(crate) fn shows_sprites(&self) -> bool {
+        self.show_sprites_in_map
+    }
+
+    fn move_camera(&mut self, delta: Vec2i) {
+        self.viewport = self.viewport.with_camera(self.viewport.camera + delta);
+    }
+
+    /// The map cell under the mouse, if it's over the map.
+    pub(crate) fn hovered_cell(&self) -> Option<(usize, usize)> {
+        if self.show_minimap && minimap_cell_at(self.mouse_position).is_some() {
+            return None;
+        }
+
+        self.viewport.cell_at(self.mouse_position)
+    }
+
+    pub(crate) fn zoom(&self) -> Zoom {
+        self.viewport.zoom
+    }
+
     pub(crate) fn subscriptions(event: &Event) -> Option<Msg> {
         match event {
             Event::Input(InputEvent::Keyboard(event)) => {
                 let KeyboardEvent { key, state } = event;
 
                 match (key, state) {
-                    (Key::C, KeyState::Down) => Some(Msg::SwitchMapMode),
+                    (Key::M, KeyState::Down) => Some(Msg::ToggleMinimap),
+                    (Key::Minus, KeyState::Down) => Some(Msg::Zoom {
+                        zoom_in: false,
+                        around_mouse: false,
+                    }),
+                    (Key::Equals, KeyState::Down) => Some(Msg::Zoom {
+                        zoom_in: true,
+                        around_mouse: false,
+                    }),
                     (Key::Space, key_state) => Some(Msg::SetDragging(*key_state == KeyState::Down)),
                     _ => None,
                 }
@@ -66,87 +371,535 @@ impl Editor {
             &Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y })) => {
                 Some(Msg::MouseMove(vec2(x, y)))
             }
+            &Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Middle,
+                state,
+            })) => Some(Msg::SetDragging(state == KeyState::Down)),
+            // Like in pico8, the vertical wheel zooms in and out.
+            &Event::Input(InputEvent::Mouse(MouseEvent::Wheel { delta_y, .. })) if delta_y != 0 => {
+                Some(Msg::Zoom {
+                    zoom_in: delta_y > 0,
+                    around_mouse: true,
+                })
+            }
+            &Event::Input(InputEvent::Mouse(MouseEvent::Wheel { delta_x, .. })) => {
+                const SCROLL_SPEED: i32 = 8;
+
+                Some(Msg::Scroll(vec2(-delta_x, 0) * SCROLL_SPEED))
+            }
             _ => None,
         }
     }
 
-    pub(crate) fn view<'a, 'b, Msg: Copy + Debug + 'a>(
+    pub(crate) fn view<'a, Msg: Copy + Debug + 'a>(
         &'a mut self,
-        map: &'b Map,
-        x: i32,
-        y: i32,
-        on_tile_click: &impl Fn(usize, usize) -> Msg,
-        on_map_editor_msg: &impl Fn(self::Msg) -> Msg,
+        map: &Map,
+        brush: &Tiles,
+        on_map_editor_msg: impl Fn(self::Msg) -> Msg + 'a,
     ) -> Element<'a, Msg> {
-        let show_sprites_in_map = self.show_sprites_in_map;
-        let camera = self.camera;
-
-        let highlighted_tile_position =
-            tile_position(self.camera, self.hovered_tile.0, self.hovered_tile.1) + vec2(x, y);
-
-        let v: Vec<Element<'_, Msg>> = self
-            .buttons
-            .iter_mut()
-            .chunks(16)
-            .into_iter()
-            .enumerate()
-            .flat_map(|(row_index, row)| {
-                row.into_iter().enumerate().map(move |(col_index, state)| {
-                    let sprite = map.mget(col_index as i32, row_index as i32);
-
-                    let Vec2i { x, y } = tile_position(camera, col_index, row_index) + vec2(x, y);
-                    Button::new(
-                        x,
-                        y,
-                        8,
-                        8,
-                        Some(on_tile_click(col_index, row_index)),
-                        state,
-                        DrawFn::new(move |draw| {
-                            draw.palt(None);
-                            if show_sprites_in_map {
-                                draw.spr(sprite.into(), 0, 0);
-                            } else {
-                                draw.print(&format!("{sprite:0>2X}"), 0, 1, 7);
-                            }
-                        }),
-                    )
-                    .event_on_press()
-                    .on_hover(on_map_editor_msg(self::Msg::HoveredTile((
-                        col_index, row_index,
-                    ))))
-                    .into()
-                })
+        let first_cell = self.viewport.first_cell();
+        let visible_cells = self.viewport.visible_cells();
+        let sprites = (0..visible_cells.y)
+            .flat_map(|row| {
+                (0..visible_cells.x)
+                    .map(move |col| map.mget(first_cell.x + col, first_cell.y + row))
             })
             .collect();
 
-        Tree::with_children(v)
-            .push(highlight_hovered(highlighted_tile_position))
-            .into()
+        // One sprite (the top left one) for every pixel.
+        let minimap = self.show_minimap.then(|| {
+            (0..MINIMAP_HEIGHT)
+                .flat_map(|y| {
+                    (0..MINIMAP_WIDTH).map(move |x| {
+                        map.mget(x * MINIMAP_CELLS_PER_PIXEL, y * MINIMAP_CELLS_PER_PIXEL)
+                    })
+                })
+                .collect()
+        });
+
+        // The hovered cells are the ones that would be painted.
+        let hovered_size = match (self.tool, &self.clipboard) {
+            (Tool::Pencil, _) => vec2(brush.width(), brush.height()),
+            (Tool::Stamp, Some(tiles)) => vec2(tiles.width(), tiles.height()),
+            _ => vec2(1, 1),
+        };
+        let hovered = self.hovered_cell().map(|(x, y)| {
+            Rect::from_corners(
+                vec2(x as i32, y as i32),
+                vec2(x as i32, y as i32) + hovered_size - vec2(1, 1),
+            )
+        });
+
+        let (marquee, floating) = match (self.tool, self.drag, self.selection) {
+            (Tool::Select, Some((from, to)), Some(selection)) if self.moving_selection => {
+                let moved = selection.moved(to - from);
+
+                (Some(moved), Some(Tiles::from_map(map, selection)))
+            }
+            (Tool::Select | Tool::Rectangle, Some((from, to)), _) => {
+                (Some(Rect::from_corners(from, to)), None)
+            }
+            (_, _, selection) => (selection, None),
+        };
+        let line = match (self.tool, self.drag) {
+            (Tool::Line, Some((from, to))) => line(from, to),
+            _ => vec![],
+        };
+
+        MapView {
+            sprites,
+            minimap,
+            viewport: self.viewport,
+            hovered,
+            marquee,
+            floating,
+            line,
+            show_sprites_in_map: self.show_sprites_in_map,
+            pressed: &mut self.pressed,
+            on_map_editor_msg,
+        }
+        .into()
     }
 }
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) enum Msg {
     SwitchMapMode,
-    HoveredTile((usize, usize)),
+    ToggleMinimap,
     MouseMove(Vec2i),
     SetDragging(bool),
+    Scroll(Vec2i),
+    Zoom {
+        zoom_in: bool,
+        around_mouse: bool,
+    },
+    CenterOn((usize, usize)),
+    ToolSelected(Tool),
+    /// The left button was pressed over a cell.
+    Press((usize, usize)),
+    /// The mouse went over a cell, while the left button is held.
+    Drag((usize, usize)),
+    /// The left button was released.
+    Release,
 }
 
-fn highlight_hovered<'a, Msg: Copy + Debug + 'a>(tile_position: Vec2i) -> Element<'a, Msg> {
-    DrawFn::new(move |draw| {
-        draw.rect(
-            tile_position.x,
-            tile_position.y,
-            tile_position.x + 7,
-            tile_position.y + 7,
-            7,
-        )
-    })
-    .into()
+/// The visible part of the map, where the tools are used.
+struct MapView<'a, F> {
+    // The sprites in the visible cells, row by row.
+    sprites: Vec<u8>,
+    minimap: Option<Vec<u8>>,
+    viewport: Viewport,
+    hovered: Option<Rect>,
+    // The selection, or the rectangle being drawn.
+    marquee: Option<Rect>,
+    // The selected tiles, while they're being moved to the marquee.
+    floating: Option<Tiles>,
+    // The cells of the line being drawn.
+    line: Vec<Vec2i>,
+    show_sprites_in_map: bool,
+    pressed: &'a mut Option<(usize, usize)>,
+    on_map_editor_msg: F,
+}
+
+impl<'a, Msg, F> Widget for MapView<'a, F>
+where
+    Msg: Copy + Debug,
+    F: Fn(self::Msg) -> Msg,
+{
+    type Msg = Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        dispatch_event: &mut DispatchEvent<Self::Msg>,
+    ) {
+        let cursor_position = vec2(cursor_position.0, cursor_position.1);
+
+        let msg = match event {
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state: KeyState::Down,
+            })) => {
+                let minimap_cell = self
+                    .minimap
+                    .as_ref()
+                    .and_then(|_| minimap_cell_at(cursor_position));
+
+                if let Some(minimap_cell) = minimap_cell {
+                    dispatch_event
+                        .call((self.on_map_editor_msg)(self::Msg::CenterOn(minimap_cell)));
+                    return;
+                }
+
+                self.viewport.cell_at(cursor_position).map(|cell| {
+                    *self.pressed = Some(cell);
+                    self::Msg::Press(cell)
+                })
+            }
+            // The cursor position lags behind mouse moves until the next tick.
+            Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y }))
+                if self.pressed.is_some() =>
+            {
+                self.viewport
+                    .cell_at(vec2(x, y))
+                    .filter(|cell| Some(*cell) != *self.pressed)
+                    .map(|cell| {
+                        *self.pressed = Some(cell);
+                        self::Msg::Drag(cell)
+                    })
+            }
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state: KeyState::Up,
+            })) => self.pressed.take().map(|_| self::Msg::Release),
+            _ => None,
+        };
+
+        if let Some(msg) = msg {
+            dispatch_event.call((self.on_map_editor_msg)(msg));
+        }
+    }
+
+    fn draw(&mut self, draw: &mut Pico8) {
+        let viewport = self.viewport;
+        let tile_size = viewport.zoom.tile_size();
+        let first_cell = viewport.first_cell();
+        let columns = viewport.visible_cells().x;
+        // Zoomed out sprites, and the minimap, show a pixel for every block of sprite pixels.
+        let downsampled = if viewport.zoom != Zoom::Full || self.minimap.is_some() {
+            downsample_sprites(&draw.resources.sprite_sheet, tile_size)
+        } else {
+            vec![]
+        };
+
+        draw.palt(None);
+        for (index, &sprite) in self.sprites.iter().enumerate() {
+            let col = (first_cell.x + index as i32 % columns) as usize;
+            let row = (first_cell.y + index as i32 / columns) as usize;
+            let position = viewport.tile_position(col, row);
+
+            self.draw_tile(draw, sprite, position, &downsampled);
+        }
+
+        if let (Some(floating), Some(marquee)) = (&self.floating, self.marquee) {
+            for (offset, sprite) in floating.iter() {
+                let position = viewport.cell_position(marquee.top_left() + offset);
+
+                self.draw_tile(draw, sprite, position, &downsampled);
+            }
+        }
+
+        if let Some(marquee) = self.marquee {
+            draw_cells_rect(draw, viewport, marquee, 10);
+        }
+        for &cell in &self.line {
+            draw_cells_rect(draw, viewport, Rect::from_corners(cell, cell), 10);
+        }
+        if let Some(hovered) = self.hovered {
+            draw_cells_rect(draw, viewport, hovered, 7);
+        }
+
+        if let Some(minimap) = &self.minimap {
+            draw_minimap(draw, minimap, &downsampled, viewport);
+        }
+    }
+}
+
+impl<'a, F> MapView<'a, F> {
+    fn draw_tile(
+        &self,
+        draw: &mut Pico8,
+        sprite: u8,
+        position: Vec2i,
+        downsampled: &[DownsampledSprite],
+    ) {
+        let Vec2i { x, y } = position;
+        let tile_size = self.viewport.zoom.tile_size();
+
+        if self.viewport.zoom != Zoom::Full {
+            let pixels = &downsampled[sprite as usize].0;
+
+            for (pixel_index, &color) in pixels.iter().enumerate() {
+                let pixel_x = pixel_index as i32 % tile_size;
+                let pixel_y = pixel_index as i32 / tile_size;
+
+                draw.pset(x + pixel_x, y + pixel_y, color);
+            }
+        } else if self.show_sprites_in_map {
+            draw.spr(sprite.into(), x, y);
+        } else {
+            draw.print(&format!("{sprite:0>2X}"), x, y + 1, 7);
+        }
+    }
+}
+
+/// Outlines a rectangle of cells.
+fn draw_cells_rect(draw: &mut Pico8, viewport: Viewport, rect: Rect, color: Color) {
+    let top_left = viewport.cell_position(rect.top_left());
+    let bottom_right = top_left + vec2(rect.width, rect.height) * viewport.zoom.tile_size();
+
+    draw.rect(
+        top_left.x,
+        top_left.y,
+        bottom_right.x - 1,
+        bottom_right.y - 1,
+        color,
+    );
+}
+
+fn draw_minimap(
+    draw: &mut Pico8,
+    minimap: &[u8],
+    downsampled: &[DownsampledSprite],
+    viewport: Viewport,
+) {
+    draw.rect(
+        MINIMAP_X - 1,
+        MINIMAP_Y - 1,
+        MINIMAP_X + MINIMAP_WIDTH,
+        MINIMAP_Y + MINIMAP_HEIGHT,
+        0,
+    );
+    for (index, &sprite) in minimap.iter().enumerate() {
+        let x = MINIMAP_X + index as i32 % MINIMAP_WIDTH;
+        let y = MINIMAP_Y + index as i32 / MINIMAP_WIDTH;
+
+        draw.pset(x, y, downsampled[sprite as usize].1);
+    }
+
+    // The part of the map shown in the view.
+    let to_minimap = |map_position: i32| map_position / SPRITE_SIZE / MINIMAP_CELLS_PER_PIXEL;
+    let view_size = vec2(VIEW_WIDTH, VIEW_HEIGHT) * viewport.scale();
+    let x0 = MINIMAP_X + to_minimap(viewport.camera.x);
+    let y0 = MINIMAP_Y + to_minimap(viewport.camera.y);
+    let x1 = MINIMAP_X + to_minimap(viewport.camera.x + view_size.x).min(MINIMAP_WIDTH) - 1;
+    let y1 = MINIMAP_Y + to_minimap(viewport.camera.y + view_size.y).min(MINIMAP_HEIGHT) - 1;
+
+    draw.rect(x0, y0, x1, y1, 7);
+}
+
+/// The map cell that a pixel of the minimap shows, if the position is on the minimap.
+fn minimap_cell_at(screen_position: Vec2i) -> Option<(usize, usize)> {
+    let local = screen_position - vec2(MINIMAP_X, MINIMAP_Y);
+    if local.x < 0 || local.y < 0 || local.x >= MINIMAP_WIDTH || local.y >= MINIMAP_HEIGHT {
+        return None;
+    }
+
+    let cell = local * MINIMAP_CELLS_PER_PIXEL;
+
+    Some((cell.x as usize, cell.y as usize))
 }
 
-fn tile_position(camera: Vec2i, col_index: usize, row_index: usize) -> Vec2i {
-    camera + vec2(col_index as i32 * 8, row_index as i32 * 8)
+/// A sprite scaled down to `size` x `size` pixels, and the color it's shown with in the minimap.
+type DownsampledSprite = (Vec<Color>, Color);
+
+fn downsample_sprites(sprite_sheet: &SpriteSheet, size: i32) -> Vec<DownsampledSprite> {
+    (0..SpriteSheet::SPRITE_COUNT)
+        .map(|index| {
+            let sprite = sprite_sheet.get_sprite(index);
+
+            (downsample(sprite, size), downsample(sprite, 1)[0])
+        })
+        .collect()
+}
+
+/// Scales a sprite down to `size` x `size` pixels,
+/// coloring each one with the most common color in the block of pixels it replaces.
+fn downsample(sprite: &Sprite, size: i32) -> Vec<Color> {
+    let block_size = SPRITE_SIZE / size;
+
+    (0..size * size)
+        .map(|index| {
+            let block_x = index % size * block_size;
+            let block_y = index / size * block_size;
+            let mut counts = [0; 16];
+
+            for y in block_y..block_y + block_size {
+                for x in block_x..block_x + block_size {
+                    counts[sprite.pget(x as isize, y as isize) as usize % 16] += 1;
+                }
+            }
+
+            // The first (lowest) color wins ties.
+            (0..16)
+                .rev()
+                .max_by_key(|&color| counts[color as usize])
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(editor: &mut Editor, msg: Msg) -> Vec<TileEdit> {
+        editor.update(msg, &Map::new(), &Tiles::from_sprite_sheet(1, 1, 1))
+    }
+
+    #[test]
+    fn camera_can_reach_the_whole_map() {
+        let mut editor = Editor::new();
+        update(
+            &mut editor,
+            Msg::MouseMove(vec2(127, VIEW_Y + VIEW_HEIGHT - 1)),
+        );
+        assert_eq!(editor.hovered_cell(), Some((15, 8)));
+
+        update(&mut editor, Msg::Scroll(vec2(-10_000, -10_000)));
+
+        assert_eq!(
+            editor.hovered_cell(),
+            Some((Map::WIDTH_SPRITES - 1, Map::HEIGHT_SPRITES - 1))
+        );
+        assert_eq!(editor.viewport.tile_position(127, 63), vec2(120, 68));
+    }
+
+    #[test]
+    fn cells_outside_of_the_view_arent_hovered() {
+        let viewport = Viewport {
+            camera: vec2(4, 4),
+            zoom: Zoom::Full,
+        };
+
+        assert_eq!(viewport.cell_at(vec2(3, VIEW_Y)), Some((0, 0)));
+        assert_eq!(viewport.cell_at(vec2(4, VIEW_Y)), Some((1, 0)));
+        assert_eq!(viewport.cell_at(vec2(64, VIEW_Y - 1)), None);
+        assert_eq!(viewport.cell_at(vec2(64, VIEW_Y + VIEW_HEIGHT)), None);
+    }
+
+    #[test]
+    fn zooming_keeps_the_hovered_cell_in_place() {
+        let mut editor = Editor::new();
+        update(&mut editor, Msg::CenterOn((64, 32)));
+        update(&mut editor, Msg::MouseMove(vec2(100, 40)));
+        let hovered_cell = editor.hovered_cell();
+
+        update(
+            &mut editor,
+            Msg::Zoom {
+                zoom_in: false,
+                around_mouse: true,
+            },
+        );
+        assert_eq!(editor.zoom(), Zoom::Half);
+        assert_eq!(editor.hovered_cell(), hovered_cell);
+
+        for _ in 0..3 {
+            update(
+                &mut editor,
+                Msg::Zoom {
+                    zoom_in: false,
+                    around_mouse: true,
+                },
+            );
+        }
+        assert_eq!(editor.zoom(), Zoom::Eighth);
+        // The whole map fits in the view, except for the (empty) space below it.
+        assert_eq!(editor.viewport.visible_cells(), vec2(128, 64));
+        assert_eq!(
+            editor.viewport.cell_at(vec2(127, VIEW_Y + 63)),
+            Some((127, 63))
+        );
+        assert_eq!(editor.viewport.cell_at(vec2(127, VIEW_Y + 64)), None);
+
+        update(
+            &mut editor,
+            Msg::Zoom {
+                zoom_in: true,
+                around_mouse: true,
+            },
+        );
+        assert_eq!(editor.zoom(), Zoom::Quarter);
+    }
+
+    #[test]
+    fn downsampling_keeps_the_most_common_color() {
+        let mut sprite_sheet = SpriteSheet::new();
+        let sprite = sprite_sheet.get_sprite_mut(1);
+        for x in 0..4 {
+            for y in 0..8 {
+                sprite.pset(x, y, 8);
+            }
+        }
+        sprite.pset(7, 7, 9);
+
+        assert_eq!(downsample(sprite, 2), [8, 0, 8, 0]);
+        assert_eq!(downsample(sprite, 1), [8]);
+        assert_eq!(downsample(sprite, 8)[63], 9);
+
+        sprite.pset(7, 7, 0);
+        // The first (lowest) color wins ties.
+        assert_eq!(downsample(sprite, 1), [0]);
+    }
+
+    #[test]
+    fn pencil_drags_paint_every_cell_in_between() {
+        let mut editor = Editor::new();
+
+        update(&mut editor, Msg::Press((0, 0)));
+        let edits = update(&mut editor, Msg::Drag((3, 0)));
+        update(&mut editor, Msg::Release);
+
+        assert_eq!(
+            edits.iter().map(|edit| edit.x).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn lines_are_painted_on_release() {
+        let mut editor = Editor::new();
+        update(&mut editor, Msg::ToolSelected(Tool::Line));
+
+        update(&mut editor, Msg::Press((0, 0)));
+        assert!(update(&mut editor, Msg::Drag((2, 2))).is_empty());
+        let edits = update(&mut editor, Msg::Release);
+
+        assert_eq!(
+            edits
+                .iter()
+                .map(|edit| (edit.x, edit.y))
+                .collect::<Vec<_>>(),
+            [(0, 0), (1, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn selections_can_be_moved_copied_and_pasted() {
+        let mut editor = Editor::new();
+        let map = Map::new();
+
+        update(&mut editor, Msg::ToolSelected(Tool::Select));
+        update(&mut editor, Msg::Press((0, 0)));
+        update(&mut editor, Msg::Drag((1, 0)));
+        assert!(update(&mut editor, Msg::Release).is_empty());
+
+        // Dragging from inside of the selection moves it.
+        update(&mut editor, Msg::Press((1, 0)));
+        update(&mut editor, Msg::Drag((1, 2)));
+        let edits = update(&mut editor, Msg::Release);
+        assert_eq!(edits.len(), 4);
+        assert_eq!(
+            edits[2..],
+            [
+                TileEdit {
+                    x: 0,
+                    y: 2,
+                    sprite: 1
+                },
+                TileEdit {
+                    x: 1,
+                    y: 2,
+                    sprite: 1
+                }
+            ]
+        );
+
+        let tiles = editor.copy(&map).unwrap();
+        assert_eq!((tiles.width(), tiles.height()), (2, 1));
+        update(&mut editor, Msg::MouseMove(vec2(64, 64)));
+        assert_eq!(editor.paste().len(), 2);
+    }
 }
@@ -0,0 +1,313 @@
+use crate::editor::clipboard;
+use crate::editor::sprite::line;
+use crate::util::rect::Rect;
+use crate::util::vec2::{vec2, Vec2i};
+use runty8_core::{Map, SpriteSheet};
+use std::collections::HashSet;
+
+/// What clicking (and dragging) on the map does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Tool {
+    /// Paints the brush on every cell the mouse goes over.
+    Pencil,
+    /// Like the pencil, but paints the copied tiles.
+    Stamp,
+    /// Selects a rectangle of cells, which can then be copied, or moved by dragging it.
+    Select,
+    /// Paints the brush along a line.
+    Line,
+    /// Fills a rectangle with the brush.
+    Rectangle,
+    /// Fills the region of identical tiles under the mouse with the brush.
+    Fill,
+}
+
+impl Tool {
+    pub(crate) const ALL: [Tool; 6] = [
+        Tool::Pencil,
+        Tool::Stamp,
+        Tool::Select,
+        Tool::Line,
+        Tool::Rectangle,
+        Tool::Fill,
+    ];
+
+    /// Editor sprite shown in the tool's button.
+    pub(crate) fn icon(self) -> usize {
+        match self {
+            Tool::Pencil => 15,
+            Tool::Stamp => 43,
+            Tool::Select => 42,
+            Tool::Line => 31,
+            Tool::Rectangle => 40,
+            Tool::Fill => 41,
+        }
+    }
+
+    pub(crate) fn to_human_readable(self) -> &'static str {
+        match self {
+            Tool::Pencil => "PENCIL",
+            Tool::Stamp => "STAMP",
+            Tool::Select => "SELECT",
+            Tool::Line => "LINE",
+            Tool::Rectangle => "RECTANGLE",
+            Tool::Fill => "FILL",
+        }
+    }
+}
+
+/// Sets a map cell to a sprite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct TileEdit {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) sprite: u8,
+}
+
+/// A rectangle of sprites, painted as a whole: a brush, or tiles copied from the map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Tiles {
+    width: i32,
+    height: i32,
+    sprites: Vec<u8>,
+}
+
+impl Tiles {
+    /// A `width` x `height` block of the sprite sheet, starting at `top_left`.
+    pub(crate) fn from_sprite_sheet(top_left: usize, width: usize, height: usize) -> Self {
+        let sprites = (0..height)
+            .flat_map(|y| (0..width).map(move |x| top_left + x + y * SpriteSheet::SPRITES_PER_ROW))
+            .map(|sprite| sprite as u8)
+            .collect();
+
+        Self {
+            width: width as i32,
+            height: height as i32,
+            sprites,
+        }
+    }
+
+    pub(crate) fn from_map(map: &Map, rect: Rect) -> Self {
+        Self {
+            width: rect.width,
+            height: rect.height,
+            sprites: rect.cells().map(|cell| map.mget(cell.x, cell.y)).collect(),
+        }
+    }
+
+    /// The tiles in pico8's `[map]` clipboard format.
+    pub(crate) fn to_clipboard_text(&self) -> String {
+        clipboard::encode(
+            "map",
+            self.width as usize,
+            self.height as usize,
+            self.sprites.iter().copied(),
+            2,
+        )
+    }
+
+    pub(crate) fn from_clipboard_text(text: &str) -> Result<Self, String> {
+        let (width, height, sprites) = clipboard::decode("map", text, 2)?;
+
+        Ok(Self {
+            width: width as i32,
+            height: height as i32,
+            sprites,
+        })
+    }
+
+    pub(crate) fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The sprite at a position, repeating the tiles in every direction.
+    fn get(&self, position: Vec2i) -> u8 {
+        let x = position.x.rem_euclid(self.width);
+        let y = position.y.rem_euclid(self.height);
+
+        self.sprites[(x + y * self.width) as usize]
+    }
+
+    /// Each sprite, along with its position relative to the top left one.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Vec2i, u8)> + '_ {
+        Rect::from_corners(Vec2i::zero(), vec2(self.width - 1, self.height - 1))
+            .cells()
+            .map(|position| (position, self.get(position)))
+    }
+
+    /// Paints the tiles with their top left corner in `cell`.
+    pub(crate) fn paint_at(&self, cell: Vec2i) -> Vec<TileEdit> {
+        self.iter()
+            .filter_map(|(position, sprite)| tile_edit(cell + position, sprite))
+            .collect()
+    }
+
+    /// Paints the tiles on every cell of the line from `from` to `to`.
+    pub(crate) fn paint_line(&self, from: Vec2i, to: Vec2i) -> Vec<TileEdit> {
+        line(from, to)
+            .into_iter()
+            .flat_map(|cell| self.paint_at(cell))
+            .collect()
+    }
+
+    /// Fills `rect` with the tiles, repeating them as needed.
+    pub(crate) fn fill_rect(&self, rect: Rect) -> Vec<TileEdit> {
+        rect.cells()
+            .filter_map(|cell| tile_edit(cell, self.get(cell - rect.top_left())))
+            .collect()
+    }
+
+    /// Fills the region of identical (and adjacent) tiles around `start` with the tiles,
+    /// repeating them from `start`.
+    pub(crate) fn flood_fill(&self, map: &Map, start: Vec2i) -> Vec<TileEdit> {
+        let target = map.mget(start.x, start.y);
+        let in_map = |cell: Vec2i| {
+            (0..Map::WIDTH_SPRITES as i32).contains(&cell.x)
+                && (0..Map::HEIGHT_SPRITES as i32).contains(&cell.y)
+        };
+
+        let mut visited = HashSet::new();
+        let mut pending = vec![start];
+        let mut edits = vec![];
+
+        while let Some(cell) = pending.pop() {
+            if !in_map(cell) || map.mget(cell.x, cell.y) != target || !visited.insert(cell) {
+                continue;
+            }
+
+            edits.extend(tile_edit(cell, self.get(cell - start)));
+            pending.extend([
+                cell + vec2(1, 0),
+                cell - vec2(1, 0),
+                cell + vec2(0, 1),
+                cell - vec2(0, 1),
+            ]);
+        }
+
+        edits
+    }
+}
+
+/// Edit for a cell, unless it's outside of the map.
+fn tile_edit(cell: Vec2i, sprite: u8) -> Option<TileEdit> {
+    let x = usize::try_from(cell.x).ok()?;
+    let y = usize::try_from(cell.y).ok()?;
+
+    (x < Map::WIDTH_SPRITES && y < Map::HEIGHT_SPRITES).then_some(TileEdit { x, y, sprite })
+}
+
+/// Moves the tiles in `from` to `to`, leaving empty cells behind.
+pub(crate) fn move_tiles(map: &Map, from: Rect, to: Rect) -> Vec<TileEdit> {
+    let tiles = Tiles::from_map(map, from);
+
+    from.cells()
+        .filter_map(|cell| tile_edit(cell, 0))
+        .chain(tiles.paint_at(to.top_left()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(map: &mut Map, edits: &[TileEdit]) {
+        for edit in edits {
+            map.mset(edit.x, edit.y, edit.sprite);
+        }
+    }
+
+    #[test]
+    fn brushes_come_from_the_sprite_sheet() {
+        let brush = Tiles::from_sprite_sheet(17, 2, 2);
+
+        assert_eq!(brush.sprites, [17, 18, 33, 34]);
+        assert_eq!(
+            brush.paint_at(vec2(127, 0)),
+            [
+                TileEdit {
+                    x: 127,
+                    y: 0,
+                    sprite: 17
+                },
+                TileEdit {
+                    x: 127,
+                    y: 1,
+                    sprite: 33
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn rectangles_repeat_the_brush() {
+        let mut map = Map::new();
+        let brush = Tiles::from_sprite_sheet(1, 2, 1);
+
+        apply(
+            &mut map,
+            &brush.fill_rect(Rect::from_corners(vec2(4, 3), vec2(2, 2))),
+        );
+
+        let tiles = Tiles::from_map(&map, Rect::from_corners(vec2(1, 2), vec2(5, 3)));
+        assert_eq!(tiles.sprites, [0, 1, 2, 1, 0, 0, 1, 2, 1, 0]);
+    }
+
+    #[test]
+    fn lines_paint_the_brush_on_every_cell() {
+        let mut map = Map::new();
+        let rect = Rect::from_corners(vec2(0, 0), vec2(3, 1));
+        apply(&mut map, &Tiles::from_sprite_sheet(0, 1, 1).fill_rect(rect));
+
+        apply(
+            &mut map,
+            &Tiles::from_sprite_sheet(3, 1, 1).paint_line(vec2(0, 0), vec2(3, 1)),
+        );
+
+        assert_eq!(
+            Tiles::from_map(&map, rect).sprites,
+            [3, 3, 0, 0, 0, 0, 3, 3]
+        );
+    }
+
+    #[test]
+    fn flood_fill_only_fills_the_region_under_the_mouse() {
+        let mut map = Map::new();
+        map.mset(0, 0, 0);
+        map.mset(1, 0, 0);
+        // A wall splitting the first 3 rows in two.
+        for y in 0..3 {
+            map.mset(2, y, 5);
+        }
+        map.mset(0, 3, 5);
+        map.mset(1, 3, 5);
+
+        let edits = Tiles::from_sprite_sheet(7, 1, 1).flood_fill(&map, vec2(0, 0));
+        apply(&mut map, &edits);
+
+        let tiles = Tiles::from_map(&map, Rect::from_corners(vec2(0, 0), vec2(3, 3)));
+        assert_eq!(
+            tiles.sprites,
+            [7, 7, 5, 0, 7, 7, 5, 0, 7, 7, 5, 0, 5, 5, 0, 0]
+        );
+    }
+
+    #[test]
+    fn moving_tiles_leaves_empty_cells_behind() {
+        let mut map = Map::new();
+        map.mset(0, 0, 1);
+        map.mset(1, 0, 2);
+        let from = Rect::from_corners(vec2(0, 0), vec2(1, 0));
+
+        let edits = move_tiles(&map, from, from.moved(vec2(1, 0)));
+        apply(&mut map, &edits);
+
+        assert_eq!(
+            Tiles::from_map(&map, Rect::from_corners(vec2(0, 0), vec2(2, 0))).sprites,
+            [0, 1, 2]
+        );
+    }
+}
@@ -8,6 +8,8 @@ pub struct State {
     timer: i32,
     enter_state: EnterState,
     content: String,
+    // Prompts stay on screen until they're dismissed.
+    sticky: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -44,14 +46,28 @@ impl State {
             timer: Left.duration(),
             enter_state: Left,
             content: "".to_owned(),
+            sticky: false,
         }
     }
 
     pub fn alert(&mut self, content: String) {
         self.content = content;
+        self.sticky = false;
         self.reset()
     }
 
+    /// Like [`State::alert`], but the notification stays until [`State::dismiss`] is called.
+    pub fn prompt(&mut self, content: String) {
+        self.content = content;
+        self.sticky = true;
+        self.reset()
+    }
+
+    pub fn dismiss(&mut self) {
+        self.sticky = false;
+        self.set_state(Leaving);
+    }
+
     fn reset(&mut self) {
         self.set_state(Entering)
     }
@@ -64,6 +80,7 @@ impl State {
     fn tick(&mut self) {
         match self.enter_state {
             Left => {}
+            Displaying if self.sticky => {}
             _ => {
                 self.timer -= 1;
                 if self.timer <= 0 {
@@ -1,16 +1,42 @@
+mod tools;
+
 use super::brush_size::{self, BrushSize, BrushSizeSelector};
+use super::zoom::Zoom;
 use crate::pico8::Pico8EditorExt as _;
 use crate::ui::{
     button::{self, Button},
-    DrawFn, Element, Tree,
+    DispatchEvent, DrawFn, Element, Tree, Widget,
+};
+use crate::util::rect::Rect;
+use crate::util::vec2::{vec2, Vec2i};
+use runty8_core::{
+    Color, Event, ImportOptions, InputEvent, Key, KeyState, KeyboardEvent, MouseButton, MouseEvent,
+    Pico8, Sprite, SpriteSheet,
 };
-use itertools::Itertools;
-use runty8_core::{Color, Sprite, SpriteSheet};
 use std::fmt::Debug;
+pub(crate) use tools::{line, PixelEdit, Pixels, Tool};
+use tools::{SHEET_HEIGHT, SHEET_WIDTH};
+
+// Where the canvas is shown on the screen.
+const CANVAS_X: i32 = 8;
+const CANVAS_Y: i32 = 11;
+const CANVAS_SIZE: i32 = 64;
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum Msg {
     ColorSelected(Color),
+    ToolSelected(Tool),
+    /// The left button was pressed over a pixel of the canvas (in sprite sheet coordinates).
+    Press(Vec2i),
+    /// The mouse went over a pixel, while the left button is held.
+    Drag(Vec2i),
+    /// The left button was released.
+    Release,
+    Zoom {
+        zoom_in: bool,
+    },
+    /// Whether the fill tool replaces the clicked color everywhere (while control is held).
+    SetReplacingColors(bool),
 }
 
 #[derive(Debug)]
@@ -18,8 +44,21 @@ pub(crate) struct Editor {
     selected_color: Color,
     color_selector_state: Vec<button::State>,
     flag_buttons: Vec<button::State>,
-    pixel_buttons: Vec<button::State>,
     brush_size_state: brush_size::State,
+    tool: Tool,
+    // Position in the sprite sheet of the top left pixel of the canvas.
+    camera: Vec2i,
+    // At 1/2, the canvas shows 2x2 sprites.
+    zoom: Zoom,
+    // The last pixel the mouse went over while the left button is held.
+    pressed: Option<Vec2i>,
+    // Where the left button was pressed, and the last pixel the mouse went over since.
+    drag: Option<(Vec2i, Vec2i)>,
+    selection: Option<Rect>,
+    // Whether the selection is being dragged around.
+    moving_selection: bool,
+    clipboard: Option<Pixels>,
+    replacing_colors: bool,
 }
 
 impl Editor {
@@ -28,26 +67,459 @@ impl Editor {
             selected_color: 0,
             color_selector_state: vec![button::State::new(); 16],
             flag_buttons: vec![button::State::new(); 8],
-            pixel_buttons: vec![button::State::new(); Sprite::WIDTH * Sprite::HEIGHT],
             brush_size_state: brush_size::State::new(),
+            tool: Tool::Pencil,
+            camera: Vec2i::zero(),
+            zoom: Zoom::Full,
+            pressed: None,
+            drag: None,
+            selection: None,
+            moving_selection: false,
+            clipboard: None,
+            replacing_colors: false,
         }
     }
-    pub(crate) fn update(&mut self, msg: Msg) {
-        match msg {
+
+    /// Handles `msg`, returning the changes it makes to the sprite sheet (when drawing, for example).
+    pub(crate) fn update(
+        &mut self,
+        msg: Msg,
+        sprite_sheet: &SpriteSheet,
+        brush_size: BrushSize,
+    ) -> Vec<PixelEdit> {
+        let canvas = self.canvas();
+        let mut edits = match msg {
             Msg::ColorSelected(selected_color) => {
                 self.selected_color = selected_color;
+
+                vec![]
+            }
+            Msg::ToolSelected(tool) => {
+                // Selecting a shape again switches between its outlined and filled versions.
+                self.tool = if tool.is_same_kind(self.tool) {
+                    self.tool.toggle_filled()
+                } else {
+                    tool
+                };
+
+                vec![]
+            }
+            Msg::Press(pixel) => self.press(pixel, sprite_sheet, brush_size),
+            Msg::Drag(pixel) => self.drag(pixel, brush_size),
+            Msg::Release => self.release(sprite_sheet),
+            Msg::Zoom { zoom_in } => {
+                self.zoom = if zoom_in {
+                    self.zoom.zoom_in()
+                } else {
+                    self.zoom.zoom_out()
+                };
+                // Keeps the canvas inside the sprite sheet.
+                self.move_camera(Vec2i::zero());
+
+                vec![]
+            }
+            Msg::SetReplacingColors(replacing_colors) => {
+                self.replacing_colors = replacing_colors;
+
+                vec![]
+            }
+        };
+
+        // Like in pico8, only the pixels in the canvas are drawn on.
+        edits.retain(|edit| canvas.contains(edit.position()));
+        edits
+    }
+
+    fn press(
+        &mut self,
+        pixel: Vec2i,
+        sprite_sheet: &SpriteSheet,
+        brush_size: BrushSize,
+    ) -> Vec<PixelEdit> {
+        self.drag = Some((pixel, pixel));
+
+        match self.tool {
+            Tool::Pencil => self.pencil(pixel, pixel, brush_size),
+            Tool::Stamp => self.stamp(pixel),
+            Tool::Fill if self.replacing_colors => {
+                let from = tools::sheet_pixel(sprite_sheet, pixel);
+                let to = self.selected_color;
+
+                self.edit_area(sprite_sheet, |sprite_sheet, (x, y), size| {
+                    sprite_sheet.replace_color(x, y, size, from, to)
+                })
+            }
+            Tool::Fill => {
+                tools::flood_fill(sprite_sheet, self.canvas(), pixel, self.selected_color)
+            }
+            Tool::Select => {
+                self.moving_selection =
+                    matches!(self.selection, Some(selection) if selection.contains(pixel));
+
+                vec![]
+            }
+            Tool::Pan | Tool::Line | Tool::Rectangle { .. } | Tool::Circle { .. } => vec![],
+        }
+    }
+
+    fn drag(&mut self, pixel: Vec2i, brush_size: BrushSize) -> Vec<PixelEdit> {
+        let (from, last) = match self.drag {
+            Some(drag) => drag,
+            None => return vec![],
+        };
+
+        match self.tool {
+            Tool::Pan => {
+                // Keeps the pixel the mouse was pressed on under it.
+                self.move_camera(from - pixel);
+
+                vec![]
+            }
+            Tool::Pencil => {
+                self.drag = Some((from, pixel));
+
+                self.pencil(last, pixel, brush_size)
+            }
+            Tool::Stamp => {
+                self.drag = Some((from, pixel));
+
+                self.stamp(pixel)
+            }
+            _ => {
+                self.drag = Some((from, pixel));
+
+                vec![]
+            }
+        }
+    }
+
+    fn release(&mut self, sprite_sheet: &SpriteSheet) -> Vec<PixelEdit> {
+        let edits = self.pending_edits(sprite_sheet);
+        let (from, to) = match self.drag.take() {
+            Some(drag) => drag,
+            None => return vec![],
+        };
+
+        match (self.tool, self.selection) {
+            (Tool::Select, Some(selection)) if self.moving_selection => {
+                self.moving_selection = false;
+                self.selection = Some(selection.moved(to - from));
+            }
+            (Tool::Select, _) => {
+                self.selection = Some(Rect::from_corners(from, to));
+            }
+            _ => {}
+        }
+
+        edits
+    }
+
+    /// The edits that releasing the mouse would make: the shape being drawn,
+    /// or the selection being moved.
+    fn pending_edits(&self, sprite_sheet: &SpriteSheet) -> Vec<PixelEdit> {
+        let (from, to) = match self.drag {
+            Some(drag) => drag,
+            None => return vec![],
+        };
+        let rect = Rect::from_corners(from, to);
+        let color = self.selected_color;
+
+        match (self.tool, self.selection) {
+            (Tool::Line, _) => tools::paint(tools::line(from, to), color),
+            (Tool::Rectangle { filled }, _) => tools::paint(tools::rectangle(rect, filled), color),
+            (Tool::Circle { filled }, _) => tools::paint(tools::ellipse(rect, filled), color),
+            (Tool::Select, Some(selection)) if self.moving_selection => {
+                tools::move_pixels(sprite_sheet, selection, selection.moved(to - from))
             }
+            _ => vec![],
         }
     }
 
+    /// Paints a line from `from` to `to` with the brush.
+    fn pencil(&self, from: Vec2i, to: Vec2i, brush_size: BrushSize) -> Vec<PixelEdit> {
+        let brush: Vec<_> = brush_size
+            .iter()
+            .map(|(x, y)| vec2(x as i32, y as i32))
+            .collect();
+
+        tools::paint(
+            tools::line(from, to)
+                .into_iter()
+                .flat_map(|pixel| brush.iter().map(move |&offset| pixel + offset)),
+            self.selected_color,
+        )
+    }
+
+    fn stamp(&self, pixel: Vec2i) -> Vec<PixelEdit> {
+        self.clipboard
+            .as_ref()
+            .map(|pixels| pixels.paint_at(pixel))
+            .unwrap_or_default()
+    }
+
+    /// Copies the selected pixels (or the whole canvas), returning them.
+    pub(crate) fn copy(&mut self, sprite_sheet: &SpriteSheet) -> Pixels {
+        let pixels =
+            Pixels::from_sprite_sheet(sprite_sheet, self.selection.unwrap_or(self.canvas()));
+        self.clipboard = Some(pixels.clone());
+
+        pixels
+    }
+
+    /// Replaces the copied pixels (with the ones in the system clipboard, for example).
+    pub(crate) fn set_clipboard(&mut self, pixels: Pixels) {
+        self.clipboard = Some(pixels);
+    }
+
+    /// Pastes the copied pixels over the selection (or the top left corner of the canvas),
+    /// and selects them.
+    pub(crate) fn paste(&mut self) -> Vec<PixelEdit> {
+        let canvas = self.canvas();
+        let target = self.selection.unwrap_or(canvas).top_left();
+
+        match &self.clipboard {
+            Some(pixels) => {
+                self.selection = Some(Rect {
+                    x: target.x,
+                    y: target.y,
+                    width: pixels.width(),
+                    height: pixels.height(),
+                });
+
+                pixels
+                    .paint_at(target)
+                    .into_iter()
+                    .filter(|edit| canvas.contains(edit.position()))
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Shows `sprite` in the canvas (in its top left corner, if the canvas fits more than one).
+    pub(crate) fn show_sprite(&mut self, sprite: usize) {
+        self.selection = None;
+        self.camera = Vec2i::zero();
+        self.move_camera(vec2(
+            (sprite % SpriteSheet::SPRITES_PER_ROW * Sprite::WIDTH) as i32,
+            (sprite / SpriteSheet::SPRITES_PER_ROW * Sprite::HEIGHT) as i32,
+        ));
+    }
+
+    /// The sprites shown in the canvas: the top left one, and how many there are
+    /// (horizontally and vertically).
+    pub(crate) fn canvas_sprites(&self) -> (usize, (usize, usize)) {
+        let canvas = self.canvas();
+        let top_left = (canvas.y as usize / Sprite::HEIGHT) * SpriteSheet::SPRITES_PER_ROW
+            + canvas.x as usize / Sprite::WIDTH;
+
+        (
+            top_left,
+            (
+                canvas.width as usize / Sprite::WIDTH,
+                canvas.height as usize / Sprite::HEIGHT,
+            ),
+        )
+    }
+
+    /// The pixels that transforms change: the selected ones in the canvas, or all of them.
+    fn edited_area(&self) -> Rect {
+        let canvas = self.canvas();
+
+        self.selection
+            .and_then(|selection| selection.intersection(canvas))
+            .unwrap_or(canvas)
+    }
+
+    /// Imports a PNG image into the selected pixels (or the whole sprite sheet),
+    /// returning the changes it makes.
+    pub(crate) fn import_png(
+        &self,
+        sprite_sheet: &SpriteSheet,
+        png: &[u8],
+        options: ImportOptions,
+    ) -> Result<Vec<PixelEdit>, String> {
+        let sheet = Rect {
+            x: 0,
+            y: 0,
+            width: SHEET_WIDTH,
+            height: SHEET_HEIGHT,
+        };
+        let area = self.selection.unwrap_or(sheet);
+        let mut edited = sprite_sheet.clone();
+
+        edited.import_png(
+            png,
+            area.x as usize,
+            area.y as usize,
+            (area.width as usize, area.height as usize),
+            options,
+        )?;
+        Ok(tools::changes(sprite_sheet, &edited, sheet))
+    }
+
+    /// Transforms the selected pixels (or the whole canvas), returning the changes it makes.
+    pub(crate) fn transform(
+        &self,
+        sprite_sheet: &SpriteSheet,
+        transform: impl FnOnce(&Pixels) -> Pixels,
+    ) -> Vec<PixelEdit> {
+        let area = self.edited_area();
+
+        transform(&Pixels::from_sprite_sheet(sprite_sheet, area)).paint_at(area.top_left())
+    }
+
+    /// Edits a copy of the sprite sheet with `edit`, which gets the top left corner and size
+    /// of the selected pixels (or the whole canvas), and returns the changes it made in the canvas.
+    fn edit_area(
+        &self,
+        sprite_sheet: &SpriteSheet,
+        edit: impl FnOnce(&mut SpriteSheet, (usize, usize), (usize, usize)),
+    ) -> Vec<PixelEdit> {
+        let area = self.edited_area();
+        let mut edited = sprite_sheet.clone();
+
+        edit(
+            &mut edited,
+            (area.x as usize, area.y as usize),
+            (area.width as usize, area.height as usize),
+        );
+        tools::changes(sprite_sheet, &edited, self.canvas())
+    }
+
+    /// Rotates the selected pixels (or the whole canvas) by 90°, clockwise.
+    /// Only squares can be rotated.
+    pub(crate) fn rotate(&self, sprite_sheet: &SpriteSheet) -> Option<Vec<PixelEdit>> {
+        let area = self.edited_area();
+
+        (area.width == area.height).then(|| {
+            self.edit_area(sprite_sheet, |sprite_sheet, (x, y), (size, _)| {
+                sprite_sheet.rotate_clockwise(x, y, size)
+            })
+        })
+    }
+
+    /// Outlines the selected pixels (or the whole canvas) with the selected color.
+    pub(crate) fn outline(&self, sprite_sheet: &SpriteSheet) -> Vec<PixelEdit> {
+        let color = self.selected_color;
+
+        self.edit_area(sprite_sheet, |sprite_sheet, (x, y), size| {
+            sprite_sheet.outline(x, y, size, color)
+        })
+    }
+
+    /// Doubles (or halves) the size of the selected pixels (or the whole canvas),
+    /// and selects the scaled ones.
+    pub(crate) fn scale(&mut self, sprite_sheet: &SpriteSheet, scale_up: bool) -> Vec<PixelEdit> {
+        let area = self.edited_area();
+        let scale = |size: i32| {
+            if scale_up {
+                size * 2
+            } else {
+                (size / 2).max(1)
+            }
+        };
+        let scaled = Rect {
+            width: scale(area.width),
+            height: scale(area.height),
+            ..area
+        };
+
+        let edits = self.edit_area(sprite_sheet, |sprite_sheet, (x, y), size| {
+            sprite_sheet.scale(x, y, size, (scaled.width as usize, scaled.height as usize))
+        });
+        self.selection = scaled.intersection(self.canvas());
+
+        edits
+    }
+
+    pub(crate) fn zoom(&self) -> Zoom {
+        self.zoom
+    }
+
+    pub(crate) fn subscriptions(event: &Event) -> Option<Msg> {
+        match event {
+            Event::Input(InputEvent::Keyboard(KeyboardEvent {
+                key,
+                state: KeyState::Down,
+            })) => match key {
+                Key::Minus => Some(Msg::Zoom { zoom_in: false }),
+                Key::Equals => Some(Msg::Zoom { zoom_in: true }),
+                Key::Control => Some(Msg::SetReplacingColors(true)),
+                _ => None,
+            },
+            Event::Input(InputEvent::Keyboard(KeyboardEvent {
+                key: Key::Control,
+                state: KeyState::Up,
+            })) => Some(Msg::SetReplacingColors(false)),
+            &Event::Input(InputEvent::Mouse(MouseEvent::Wheel { delta_y, .. })) if delta_y != 0 => {
+                Some(Msg::Zoom {
+                    zoom_in: delta_y > 0,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The tools, as they'd be selected (a shape tool is filled if it was last used filled).
+    pub(crate) fn tools(&self) -> [Tool; 8] {
+        Tool::ALL.map(|tool| {
+            if tool.is_same_kind(self.tool) {
+                self.tool
+            } else {
+                tool
+            }
+        })
+    }
+
+    pub(crate) fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    /// The part of the sprite sheet shown (and edited) in the canvas.
+    fn canvas(&self) -> Rect {
+        let sprites = Sprite::WIDTH as i32 / self.zoom.tile_size();
+
+        Rect {
+            x: self.camera.x,
+            y: self.camera.y,
+            width: sprites * Sprite::WIDTH as i32,
+            height: sprites * Sprite::HEIGHT as i32,
+        }
+    }
+
+    fn move_camera(&mut self, delta: Vec2i) {
+        let canvas = self.canvas();
+        let camera = self.camera + delta;
+
+        self.camera = vec2(
+            camera.x.clamp(0, SHEET_WIDTH - canvas.width),
+            camera.y.clamp(0, SHEET_HEIGHT - canvas.height),
+        );
+    }
+
     pub(crate) fn view<'a>(
         &'a mut self,
         selected_sprite_flags: u8,
-        selected_sprite: &Sprite,
+        sprite_sheet: &SpriteSheet,
         editor_sprites: &'a SpriteSheet,
         brush_size: BrushSize,
-        to_editor_msg: &(impl Fn(Msg) -> super::Msg + Copy),
+        to_editor_msg: &(impl Fn(Msg) -> super::Msg + Copy + 'a),
     ) -> Element<'a, super::Msg> {
+        let area = self.canvas();
+        let pixels = area
+            .cells()
+            .map(|pixel| tools::sheet_pixel(sprite_sheet, pixel))
+            .collect();
+        let preview = self.pending_edits(sprite_sheet);
+        let marquee = match (self.tool, self.drag, self.selection) {
+            (Tool::Select, Some((from, to)), Some(selection)) if self.moving_selection => {
+                Some(selection.moved(to - from))
+            }
+            (Tool::Select, Some((from, to)), _) => Some(Rect::from_corners(from, to)),
+            (_, _, selection) => selection,
+        };
+
         Tree::new()
             .push(color_selector(
                 79,
@@ -58,13 +530,14 @@ impl Editor {
                 move |color| to_editor_msg(Msg::ColorSelected(color)),
                 super::Msg::ColorHovered,
             ))
-            .push(canvas_view(
-                7,
-                10,
-                self.selected_color,
-                &mut self.pixel_buttons,
-                selected_sprite,
-            ))
+            .push(Canvas {
+                pixels,
+                area,
+                preview,
+                marquee,
+                pressed: &mut self.pressed,
+                to_editor_msg: *to_editor_msg,
+            })
             .push(flags(
                 selected_sprite_flags,
                 78,
@@ -216,53 +689,270 @@ fn flags<'a>(
     Tree::with_children(children).into()
 }
 
-fn canvas_view<'a>(
-    x: i32,
-    y: i32,
-    selected_color: Color,
-    pixel_buttons: &'a mut [button::State],
-    sprite: &Sprite,
-) -> Element<'a, super::Msg> {
-    let mut elements = vec![];
+/// The pixels being edited, zoomed in.
+struct Canvas<'a, F> {
+    // The colors of the pixels in `area`, row by row.
+    pixels: Vec<Color>,
+    // The part of the sprite sheet shown.
+    area: Rect,
+    // What the shape being drawn (or the selection being moved) would look like.
+    preview: Vec<PixelEdit>,
+    // The selection, or the rectangle being selected.
+    marquee: Option<Rect>,
+    pressed: &'a mut Option<Vec2i>,
+    to_editor_msg: F,
+}
 
-    for (y_index, chunk) in pixel_buttons
-        .iter_mut()
-        .zip(sprite.iter())
-        .chunks(8)
-        .into_iter()
-        .enumerate()
-    {
-        let y = y + 1 + (y_index * Sprite::HEIGHT) as i32;
-        for (x_index, (button, pixel_color)) in chunk.enumerate() {
-            let x = x + 1 + (x_index * Sprite::WIDTH) as i32;
-
-            elements.push(
-                Button::new(
-                    x,
-                    y,
-                    Sprite::WIDTH as i32,
-                    Sprite::HEIGHT as i32,
-                    Some(super::Msg::SpriteEdited {
-                        x: x_index,
-                        y: y_index,
-                        color: selected_color,
-                    }),
-                    button,
-                    DrawFn::new(move |draw| {
-                        draw.palt(None);
-                        draw.rectfill(0, 0, 7, 7, pixel_color);
-                    }),
-                )
-                .event_on_press()
-                .into(),
-            )
+impl<'a, F> Canvas<'a, F> {
+    // Size of a pixel on the screen.
+    fn pixel_size(&self) -> i32 {
+        CANVAS_SIZE / self.area.width
+    }
+
+    /// The pixel at a position on the screen, if the canvas is shown there.
+    fn pixel_at(&self, screen_position: Vec2i) -> Option<Vec2i> {
+        let local = screen_position - vec2(CANVAS_X, CANVAS_Y);
+        let on_canvas = (0..CANVAS_SIZE).contains(&local.x) && (0..CANVAS_SIZE).contains(&local.y);
+
+        on_canvas.then(|| self.area.top_left() + local / self.pixel_size())
+    }
+
+    /// The pixel closest to a position on the screen, so that shapes can be dragged
+    /// to the edges of the canvas.
+    fn closest_pixel(&self, screen_position: Vec2i) -> Vec2i {
+        let clamp = |position: i32| position.clamp(0, CANVAS_SIZE - 1);
+        let local = screen_position - vec2(CANVAS_X, CANVAS_Y);
+
+        self.pixel_at(vec2(CANVAS_X + clamp(local.x), CANVAS_Y + clamp(local.y)))
+            .unwrap()
+    }
+
+    fn screen_position(&self, pixel: Vec2i) -> Vec2i {
+        vec2(CANVAS_X, CANVAS_Y) + (pixel - self.area.top_left()) * self.pixel_size()
+    }
+}
+
+impl<'a, F> Widget for Canvas<'a, F>
+where
+    F: Fn(Msg) -> super::Msg,
+{
+    type Msg = super::Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        dispatch_event: &mut DispatchEvent<Self::Msg>,
+    ) {
+        let msg = match event {
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state: KeyState::Down,
+            })) => self
+                .pixel_at(vec2(cursor_position.0, cursor_position.1))
+                .map(|pixel| {
+                    *self.pressed = Some(pixel);
+                    Msg::Press(pixel)
+                }),
+            // The cursor position lags behind mouse moves until the next tick.
+            Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y }))
+                if self.pressed.is_some() =>
+            {
+                let pixel = self.closest_pixel(vec2(x, y));
+
+                (Some(pixel) != *self.pressed).then(|| {
+                    *self.pressed = Some(pixel);
+                    Msg::Drag(pixel)
+                })
+            }
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state: KeyState::Up,
+            })) => self.pressed.take().map(|_| Msg::Release),
+            _ => None,
+        };
+
+        if let Some(msg) = msg {
+            dispatch_event.call((self.to_editor_msg)(msg));
         }
     }
 
-    let highlight = DrawFn::new(move |draw| {
+    fn draw(&mut self, draw: &mut Pico8) {
+        let pixel_size = self.pixel_size();
+        let fill_pixel = |draw: &mut Pico8, position: Vec2i, color| {
+            draw.rectfill(
+                position.x,
+                position.y,
+                position.x + pixel_size - 1,
+                position.y + pixel_size - 1,
+                color,
+            );
+        };
+
         draw.palt(None);
-        draw.rect(x, y, x + 64 + 1, y + 64 + 1, 0)
-    });
+        for (pixel, &color) in self.area.cells().zip(self.pixels.iter()) {
+            fill_pixel(draw, self.screen_position(pixel), color);
+        }
+        for edit in &self.preview {
+            if self.area.contains(edit.position()) {
+                fill_pixel(draw, self.screen_position(edit.position()), edit.color);
+            }
+        }
+
+        if let Some(marquee) = self
+            .marquee
+            .and_then(|marquee| marquee.intersection(self.area))
+        {
+            let top_left = self.screen_position(marquee.top_left());
+            let size = vec2(marquee.width, marquee.height) * pixel_size;
+
+            draw.rect(
+                top_left.x,
+                top_left.y,
+                top_left.x + size.x - 1,
+                top_left.y + size.y - 1,
+                10,
+            );
+        }
+
+        draw.rect(
+            CANVAS_X - 1,
+            CANVAS_Y - 1,
+            CANVAS_X + CANVAS_SIZE,
+            CANVAS_Y + CANVAS_SIZE,
+            0,
+        );
+        draw.palt(Some(0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(editor: &mut Editor, sprite_sheet: &mut SpriteSheet, msg: Msg) {
+        for edit in editor.update(msg, sprite_sheet, BrushSize::tiny()) {
+            sprite_sheet.set(edit.x, edit.y, edit.color);
+        }
+    }
+
+    #[test]
+    fn shapes_are_drawn_when_the_mouse_is_released() {
+        let mut editor = Editor::new();
+        let mut sprite_sheet = SpriteSheet::new();
+
+        update(&mut editor, &mut sprite_sheet, Msg::ColorSelected(7));
+        update(
+            &mut editor,
+            &mut sprite_sheet,
+            Msg::ToolSelected(Tool::Line),
+        );
+        update(&mut editor, &mut sprite_sheet, Msg::Press(vec2(0, 0)));
+        update(&mut editor, &mut sprite_sheet, Msg::Drag(vec2(20, 0)));
+        assert_eq!(sprite_sheet.get(1, 0), 0);
+
+        update(&mut editor, &mut sprite_sheet, Msg::Release);
+        // Clipped to the canvas.
+        assert_eq!(sprite_sheet.get(7, 0), 7);
+        assert_eq!(sprite_sheet.get(8, 0), 0);
+    }
+
+    #[test]
+    fn selections_can_be_moved() {
+        let mut editor = Editor::new();
+        let mut sprite_sheet = SpriteSheet::new();
+        sprite_sheet.set(1, 1, 8);
+
+        update(
+            &mut editor,
+            &mut sprite_sheet,
+            Msg::ToolSelected(Tool::Select),
+        );
+        update(&mut editor, &mut sprite_sheet, Msg::Press(vec2(1, 1)));
+        update(&mut editor, &mut sprite_sheet, Msg::Release);
+        update(&mut editor, &mut sprite_sheet, Msg::Press(vec2(1, 1)));
+        update(&mut editor, &mut sprite_sheet, Msg::Drag(vec2(3, 2)));
+        update(&mut editor, &mut sprite_sheet, Msg::Release);
+
+        assert_eq!(sprite_sheet.get(1, 1), 0);
+        assert_eq!(sprite_sheet.get(3, 2), 8);
+        assert_eq!(
+            editor.selection,
+            Some(Rect::from_corners(vec2(3, 2), vec2(3, 2)))
+        );
+    }
+
+    #[test]
+    fn filling_while_holding_control_replaces_the_color_everywhere() {
+        let mut editor = Editor::new();
+        let mut sprite_sheet = SpriteSheet::new();
+        sprite_sheet.set(0, 0, 8);
+        sprite_sheet.set(7, 7, 8);
+        // Outside of the canvas.
+        sprite_sheet.set(8, 0, 8);
 
-    Tree::with_children(elements).push(highlight).into()
+        update(&mut editor, &mut sprite_sheet, Msg::ColorSelected(2));
+        update(
+            &mut editor,
+            &mut sprite_sheet,
+            Msg::ToolSelected(Tool::Fill),
+        );
+        update(
+            &mut editor,
+            &mut sprite_sheet,
+            Msg::SetReplacingColors(true),
+        );
+        update(&mut editor, &mut sprite_sheet, Msg::Press(vec2(0, 0)));
+
+        assert_eq!(sprite_sheet.get(0, 0), 2);
+        assert_eq!(sprite_sheet.get(7, 7), 2);
+        assert_eq!(sprite_sheet.get(1, 0), 0);
+        assert_eq!(sprite_sheet.get(8, 0), 8);
+    }
+
+    #[test]
+    fn scaling_selects_the_scaled_pixels() {
+        let mut editor = Editor::new();
+        let mut sprite_sheet = SpriteSheet::new();
+        sprite_sheet.set(1, 1, 8);
+
+        update(
+            &mut editor,
+            &mut sprite_sheet,
+            Msg::ToolSelected(Tool::Select),
+        );
+        update(&mut editor, &mut sprite_sheet, Msg::Press(vec2(1, 1)));
+        update(&mut editor, &mut sprite_sheet, Msg::Drag(vec2(2, 1)));
+        update(&mut editor, &mut sprite_sheet, Msg::Release);
+        for edit in editor.scale(&sprite_sheet, true) {
+            sprite_sheet.set(edit.x, edit.y, edit.color);
+        }
+
+        assert_eq!(
+            editor.selection,
+            Some(Rect::from_corners(vec2(1, 1), vec2(4, 2)))
+        );
+        assert_eq!(
+            Pixels::from_sprite_sheet(&sprite_sheet, editor.selection.unwrap())
+                .iter()
+                .map(|(_, color)| color)
+                .collect::<Vec<_>>(),
+            [8, 8, 0, 0, 8, 8, 0, 0]
+        );
+    }
+
+    #[test]
+    fn panning_keeps_the_canvas_in_the_sprite_sheet() {
+        let mut editor = Editor::new();
+        let mut sprite_sheet = SpriteSheet::new();
+        editor.show_sprite(17);
+
+        update(&mut editor, &mut sprite_sheet, Msg::ToolSelected(Tool::Pan));
+        update(&mut editor, &mut sprite_sheet, Msg::Press(vec2(10, 10)));
+        update(&mut editor, &mut sprite_sheet, Msg::Drag(vec2(12, 9)));
+        assert_eq!(editor.camera, vec2(6, 9));
+
+        update(&mut editor, &mut sprite_sheet, Msg::Drag(vec2(30, 30)));
+        assert_eq!(editor.camera, vec2(0, 0));
+    }
 }
@@ -0,0 +1,447 @@
+use crate::editor::clipboard;
+use crate::util::rect::Rect;
+use crate::util::vec2::{vec2, Vec2i};
+use runty8_core::{Color, Sprite, SpriteSheet};
+use std::collections::HashSet;
+
+// Size of the sprite sheet, in pixels.
+pub(crate) const SHEET_WIDTH: i32 = (SpriteSheet::SPRITES_PER_ROW * Sprite::WIDTH) as i32;
+pub(crate) const SHEET_HEIGHT: i32 =
+    (SpriteSheet::SPRITE_COUNT / SpriteSheet::SPRITES_PER_ROW * Sprite::HEIGHT) as i32;
+
+/// What clicking (and dragging) on the canvas does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Tool {
+    /// Paints with the brush wherever the mouse goes.
+    Pencil,
+    /// Like the pencil, but paints the copied pixels.
+    Stamp,
+    /// Selects a rectangle of pixels, which can then be copied, or moved by dragging it.
+    Select,
+    /// Moves the canvas around the sprite sheet.
+    Pan,
+    /// Fills the region of identical pixels under the mouse.
+    Fill,
+    Line,
+    Rectangle {
+        filled: bool,
+    },
+    Circle {
+        filled: bool,
+    },
+}
+
+impl Tool {
+    pub(crate) const ALL: [Tool; 8] = [
+        Tool::Pencil,
+        Tool::Stamp,
+        Tool::Select,
+        Tool::Pan,
+        Tool::Fill,
+        Tool::Line,
+        Tool::Rectangle { filled: false },
+        Tool::Circle { filled: false },
+    ];
+
+    /// Editor sprite shown in the tool's button.
+    pub(crate) fn icon(self) -> usize {
+        match self {
+            Tool::Pencil => 15,
+            Tool::Stamp => 43,
+            Tool::Select => 42,
+            Tool::Pan => 45,
+            Tool::Fill => 41,
+            Tool::Line => 31,
+            Tool::Rectangle { filled: false } => 40,
+            Tool::Rectangle { filled: true } => 47,
+            Tool::Circle { filled: false } => 44,
+            Tool::Circle { filled: true } => 50,
+        }
+    }
+
+    pub(crate) fn to_human_readable(self) -> &'static str {
+        match self {
+            Tool::Pencil => "PENCIL",
+            Tool::Stamp => "STAMP",
+            Tool::Select => "SELECT",
+            Tool::Pan => "PAN",
+            Tool::Fill => "FILL",
+            Tool::Line => "LINE",
+            Tool::Rectangle { filled: false } => "RECTANGLE",
+            Tool::Rectangle { filled: true } => "FILLED RECTANGLE",
+            Tool::Circle { filled: false } => "CIRCLE",
+            Tool::Circle { filled: true } => "FILLED CIRCLE",
+        }
+    }
+
+    /// Whether both are the same tool, filled or not.
+    pub(crate) fn is_same_kind(self, other: Tool) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+
+    /// The filled version of a shape tool, or the outlined one if it's already filled.
+    pub(crate) fn toggle_filled(self) -> Self {
+        match self {
+            Tool::Rectangle { filled } => Tool::Rectangle { filled: !filled },
+            Tool::Circle { filled } => Tool::Circle { filled: !filled },
+            tool => tool,
+        }
+    }
+}
+
+/// Sets a pixel of the sprite sheet to a color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PixelEdit {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) color: Color,
+}
+
+impl PixelEdit {
+    pub(crate) fn position(self) -> Vec2i {
+        vec2(self.x as i32, self.y as i32)
+    }
+}
+
+/// Edit for a pixel, unless it's outside of the sprite sheet.
+pub(crate) fn pixel_edit(position: Vec2i, color: Color) -> Option<PixelEdit> {
+    let x = usize::try_from(position.x).ok()?;
+    let y = usize::try_from(position.y).ok()?;
+
+    (position.x < SHEET_WIDTH && position.y < SHEET_HEIGHT).then_some(PixelEdit { x, y, color })
+}
+
+/// Paints every pixel in `positions` with `color`.
+pub(crate) fn paint(positions: impl IntoIterator<Item = Vec2i>, color: Color) -> Vec<PixelEdit> {
+    positions
+        .into_iter()
+        .filter_map(|position| pixel_edit(position, color))
+        .collect()
+}
+
+/// A rectangle of pixels copied from the sprite sheet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Pixels {
+    width: i32,
+    height: i32,
+    colors: Vec<Color>,
+}
+
+impl Pixels {
+    pub(crate) fn from_sprite_sheet(sprite_sheet: &SpriteSheet, rect: Rect) -> Self {
+        Self {
+            width: rect.width,
+            height: rect.height,
+            colors: rect
+                .cells()
+                .map(|position| sheet_pixel(sprite_sheet, position))
+                .collect(),
+        }
+    }
+
+    /// The pixels in pico8's `[gfx]` clipboard format.
+    pub(crate) fn to_clipboard_text(&self) -> String {
+        clipboard::encode(
+            "gfx",
+            self.width as usize,
+            self.height as usize,
+            self.colors.iter().copied(),
+            1,
+        )
+    }
+
+    pub(crate) fn from_clipboard_text(text: &str) -> Result<Self, String> {
+        let (width, height, colors) = clipboard::decode("gfx", text, 1)?;
+
+        Ok(Self {
+            width: width as i32,
+            height: height as i32,
+            colors,
+        })
+    }
+
+    pub(crate) fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Each color, along with its position relative to the top left pixel.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Vec2i, Color)> + '_ {
+        Rect::from_corners(Vec2i::zero(), vec2(self.width - 1, self.height - 1))
+            .cells()
+            .zip(self.colors.iter().copied())
+    }
+
+    /// The color at a position, repeating the pixels in every direction.
+    fn get(&self, position: Vec2i) -> Color {
+        let x = position.x.rem_euclid(self.width);
+        let y = position.y.rem_euclid(self.height);
+
+        self.colors[(x + y * self.width) as usize]
+    }
+
+    /// Pixels of the same size, taking the color of each one from the pixel at `source(position)`.
+    fn remapped(&self, source: impl Fn(Vec2i) -> Vec2i) -> Self {
+        Self {
+            colors: self
+                .iter()
+                .map(|(position, _)| self.get(source(position)))
+                .collect(),
+            ..*self
+        }
+    }
+
+    pub(crate) fn flipped_horizontally(&self) -> Self {
+        self.remapped(|position| vec2(self.width - 1 - position.x, position.y))
+    }
+
+    pub(crate) fn flipped_vertically(&self) -> Self {
+        self.remapped(|position| vec2(position.x, self.height - 1 - position.y))
+    }
+
+    /// Moves the pixels by `offset`, wrapping the ones that go over an edge around to the other.
+    pub(crate) fn shifted(&self, offset: Vec2i) -> Self {
+        self.remapped(|position| position - offset)
+    }
+
+    /// Paints the pixels with their top left corner in `position`.
+    pub(crate) fn paint_at(&self, position: Vec2i) -> Vec<PixelEdit> {
+        self.iter()
+            .filter_map(|(offset, color)| pixel_edit(position + offset, color))
+            .collect()
+    }
+}
+
+/// The color of a pixel of the sprite sheet, or 0 outside of it.
+pub(crate) fn sheet_pixel(sprite_sheet: &SpriteSheet, position: Vec2i) -> Color {
+    match pixel_edit(position, 0) {
+        Some(PixelEdit { x, y, .. }) => sprite_sheet.get(x, y),
+        None => 0,
+    }
+}
+
+/// The pixels in a line from `from` to `to` (both included), using Bresenham's algorithm.
+pub(crate) fn line(from: Vec2i, to: Vec2i) -> Vec<Vec2i> {
+    let dx = (to.x - from.x).abs();
+    let dy = -(to.y - from.y).abs();
+    let step = vec2((to.x - from.x).signum(), (to.y - from.y).signum());
+    let mut error = dx + dy;
+    let mut position = from;
+    let mut pixels = vec![position];
+
+    while position != to {
+        let double_error = 2 * error;
+        if double_error >= dy {
+            error += dy;
+            position.x += step.x;
+        }
+        if double_error <= dx {
+            error += dx;
+            position.y += step.y;
+        }
+        pixels.push(position);
+    }
+
+    pixels
+}
+
+/// The pixels in the border of `rect`, or all of them if it's `filled`.
+pub(crate) fn rectangle(rect: Rect, filled: bool) -> Vec<Vec2i> {
+    let on_border = |position: Vec2i| {
+        position.x == rect.x
+            || position.y == rect.y
+            || position.x == rect.x + rect.width - 1
+            || position.y == rect.y + rect.height - 1
+    };
+
+    rect.cells()
+        .filter(|&position| filled || on_border(position))
+        .collect()
+}
+
+/// The pixels in the outline of the ellipse that fits in `rect`, or all of them if it's `filled`.
+pub(crate) fn ellipse(rect: Rect, filled: bool) -> Vec<Vec2i> {
+    // Compares doubled distances to the center, so that even sizes don't need fractions.
+    let (width, height) = (i64::from(rect.width), i64::from(rect.height));
+    let inside = |position: Vec2i| {
+        let dx = 2 * i64::from(position.x - rect.x) + 1 - width;
+        let dy = 2 * i64::from(position.y - rect.y) + 1 - height;
+
+        rect.contains(position)
+            && dx * dx * height * height + dy * dy * width * width
+                <= width * width * height * height
+    };
+    let on_outline = |position: Vec2i| {
+        [vec2(1, 0), vec2(-1, 0), vec2(0, 1), vec2(0, -1)]
+            .iter()
+            .any(|&offset| !inside(position + offset))
+    };
+
+    rect.cells()
+        .filter(|&position| inside(position) && (filled || on_outline(position)))
+        .collect()
+}
+
+/// Fills the region of identical (and adjacent) pixels around `start` with `color`,
+/// without leaving `area`.
+pub(crate) fn flood_fill(
+    sprite_sheet: &SpriteSheet,
+    area: Rect,
+    start: Vec2i,
+    color: Color,
+) -> Vec<PixelEdit> {
+    let target = sheet_pixel(sprite_sheet, start);
+    let mut visited = HashSet::new();
+    let mut pending = vec![start];
+    let mut filled = vec![];
+
+    while let Some(position) = pending.pop() {
+        if !area.contains(position)
+            || sheet_pixel(sprite_sheet, position) != target
+            || !visited.insert(position)
+        {
+            continue;
+        }
+
+        filled.push(position);
+        pending.extend([
+            position + vec2(1, 0),
+            position - vec2(1, 0),
+            position + vec2(0, 1),
+            position - vec2(0, 1),
+        ]);
+    }
+
+    paint(filled, color)
+}
+
+/// The pixels in `area` that are different in `edited`, as edits to the original sprite sheet.
+pub(crate) fn changes(
+    sprite_sheet: &SpriteSheet,
+    edited: &SpriteSheet,
+    area: Rect,
+) -> Vec<PixelEdit> {
+    area.cells()
+        .filter(|&position| sheet_pixel(sprite_sheet, position) != sheet_pixel(edited, position))
+        .filter_map(|position| pixel_edit(position, sheet_pixel(edited, position)))
+        .collect()
+}
+
+/// Moves the pixels in `from` to `to`, leaving transparent (0) pixels behind.
+pub(crate) fn move_pixels(sprite_sheet: &SpriteSheet, from: Rect, to: Rect) -> Vec<PixelEdit> {
+    let pixels = Pixels::from_sprite_sheet(sprite_sheet, from);
+
+    paint(from.cells(), 0)
+        .into_iter()
+        .chain(pixels.paint_at(to.top_left()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(sprite_sheet: &mut SpriteSheet, edits: &[PixelEdit]) {
+        for edit in edits {
+            sprite_sheet.set(edit.x, edit.y, edit.color);
+        }
+    }
+
+    /// The pixels of `rect` as a string, a row per line, with `#` for the ones in `pixels`.
+    fn picture(rect: Rect, pixels: &[Vec2i]) -> String {
+        (rect.y..rect.y + rect.height)
+            .map(|y| {
+                (rect.x..rect.x + rect.width)
+                    .map(|x| {
+                        if pixels.contains(&vec2(x, y)) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn lines_go_through_every_step() {
+        let pixels = line(vec2(0, 0), vec2(4, 2));
+
+        assert_eq!(
+            pixels,
+            [vec2(0, 0), vec2(1, 1), vec2(2, 1), vec2(3, 2), vec2(4, 2)]
+        );
+        assert_eq!(line(vec2(3, 3), vec2(3, 3)), [vec2(3, 3)]);
+    }
+
+    #[test]
+    fn shapes_can_be_outlined_or_filled() {
+        let rect = Rect::from_corners(vec2(0, 0), vec2(4, 3));
+
+        assert_eq!(
+            picture(rect, &rectangle(rect, false)),
+            "#####\n#...#\n#...#\n#####"
+        );
+        assert_eq!(
+            picture(rect, &rectangle(rect, true)),
+            "#####\n#####\n#####\n#####"
+        );
+
+        let rect = Rect::from_corners(vec2(0, 0), vec2(6, 6));
+        assert_eq!(
+            picture(rect, &ellipse(rect, false)),
+            "..###..\n.#...#.\n#.....#\n#.....#\n#.....#\n.#...#.\n..###.."
+        );
+        assert_eq!(
+            picture(rect, &ellipse(rect, true)),
+            "..###..\n.#####.\n#######\n#######\n#######\n.#####.\n..###.."
+        );
+    }
+
+    #[test]
+    fn flood_fill_stays_in_the_area() {
+        let mut sprite_sheet = SpriteSheet::new();
+        // A wall splitting the first sprite in two.
+        apply(&mut sprite_sheet, &paint(line(vec2(3, 0), vec2(3, 7)), 5));
+
+        let area = Rect::from_corners(vec2(0, 0), vec2(7, 7));
+        let edits = flood_fill(&sprite_sheet, area, vec2(0, 0), 8);
+
+        assert_eq!(edits.len(), 3 * 8);
+        assert!(edits.iter().all(|edit| edit.x < 3 && edit.y < 8));
+    }
+
+    #[test]
+    fn pixels_can_be_flipped_and_shifted() {
+        let pixels = Pixels {
+            width: 3,
+            height: 2,
+            colors: vec![1, 2, 3, 4, 5, 6],
+        };
+
+        assert_eq!(pixels.flipped_horizontally().colors, [3, 2, 1, 6, 5, 4]);
+        assert_eq!(pixels.flipped_vertically().colors, [4, 5, 6, 1, 2, 3]);
+        assert_eq!(pixels.shifted(vec2(1, 0)).colors, [3, 1, 2, 6, 4, 5]);
+        assert_eq!(pixels.shifted(vec2(0, -1)).colors, [4, 5, 6, 1, 2, 3]);
+    }
+
+    #[test]
+    fn moving_pixels_leaves_transparent_ones_behind() {
+        let mut sprite_sheet = SpriteSheet::new();
+        apply(&mut sprite_sheet, &paint([vec2(0, 0)], 1));
+        apply(&mut sprite_sheet, &paint([vec2(1, 0)], 2));
+        let from = Rect::from_corners(vec2(0, 0), vec2(1, 0));
+
+        let edits = move_pixels(&sprite_sheet, from, from.moved(vec2(1, 0)));
+        apply(&mut sprite_sheet, &edits);
+
+        let moved =
+            Pixels::from_sprite_sheet(&sprite_sheet, Rect::from_corners(vec2(0, 0), vec2(2, 0)));
+        assert_eq!(moved.colors, [0, 1, 2]);
+    }
+}
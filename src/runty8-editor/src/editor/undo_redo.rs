@@ -1,11 +1,16 @@
-use runty8_core::SpriteSheet;
+use runty8_core::Resources;
 
 use super::notification;
 
+/// How many commands can be undone, older ones are forgotten.
+const MAX_HISTORY: usize = 256;
+
 #[derive(Debug)]
 pub(crate) struct Commands {
     commands: Vec<Command>,
     current: usize,
+    // Commands that will be undone together, see `Commands::push_grouped`.
+    group: Vec<Command>,
 }
 
 impl Commands {
@@ -13,45 +18,97 @@ impl Commands {
         Self {
             commands: vec![],
             current: 0,
+            group: vec![],
         }
     }
+
     pub fn push(&mut self, command: Command) {
+        self.close_group();
+        self.push_command(command);
+    }
+
+    /// Adds a command to the current group (e.g, a stroke, from mouse down to mouse up),
+    /// which is undone as a whole after [`Commands::close_group`].
+    pub fn push_grouped(&mut self, command: Command) {
+        self.group.push(command);
+    }
+
+    pub fn close_group(&mut self) {
+        match self.group.len() {
+            0 => {}
+            1 => {
+                let command = self.group.pop().unwrap();
+                self.push_command(command);
+            }
+            _ => {
+                let group = std::mem::take(&mut self.group);
+                self.push_command(Command::Group(group));
+            }
+        }
+    }
+
+    fn push_command(&mut self, command: Command) {
         self.commands.drain(self.current..self.commands.len());
 
         self.commands.push(command);
         self.current += 1;
+
+        if self.commands.len() > MAX_HISTORY {
+            self.commands.remove(0);
+            self.current -= 1;
+        }
     }
 
-    pub fn undo(&mut self, notification: &mut notification::State, sprite_sheet: &mut SpriteSheet) {
+    /// Undoes the last command, returning it.
+    pub fn undo(
+        &mut self,
+        notification: &mut notification::State,
+        resources: &mut Resources,
+    ) -> Option<&Command> {
+        self.close_group();
+
         if self.current > 0 {
             let command = &self.commands[self.current - 1];
             self.current -= 1;
 
-            command.undo(sprite_sheet);
+            command.undo(resources);
+            Some(command)
         } else {
             notification.alert("NOTHING TO UNDO".to_owned());
+            None
         }
     }
 
-    pub fn redo(&mut self, notification: &mut notification::State, sprite_sheet: &mut SpriteSheet) {
+    /// Redoes the last undone command, returning it.
+    pub fn redo(
+        &mut self,
+        notification: &mut notification::State,
+        resources: &mut Resources,
+    ) -> Option<&Command> {
+        self.close_group();
+
         if self.current < self.commands.len() {
             let command = &self.commands[self.current];
             self.current += 1;
 
-            command.redo(sprite_sheet);
+            command.redo(resources);
+            Some(command)
         } else {
             notification.alert("NOTHING TO REDO".to_owned());
+            None
         }
     }
 }
 
 // Undoable actions
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Command {
-    // TODO: This currently treats each edited pixel as its own action.
-    // Pico8 instead tracks "strokes", i.e, drawing with the pen until you lift it
-    // counts as a single command/undoable action. We should do that.
     PixelChanged(PixelChanged),
+    MapCellChanged(MapCellChanged),
+    FlagChanged(FlagChanged),
+    /// Commands undone (and redone) together, like the pixels drawn in a single stroke.
+    Group(Vec<Command>),
 }
 
 impl Command {
@@ -71,15 +128,47 @@ impl Command {
         })
     }
 
-    fn undo(&self, sprite_sheet: &mut SpriteSheet) {
+    pub fn map_cell_changed(x: usize, y: usize, previous_sprite: u8, new_sprite: u8) -> Self {
+        Self::MapCellChanged(MapCellChanged {
+            x,
+            y,
+            previous_sprite,
+            new_sprite,
+        })
+    }
+
+    pub fn flag_changed(sprite: usize, flag: usize, previous_value: bool, new_value: bool) -> Self {
+        Self::FlagChanged(FlagChanged {
+            sprite,
+            flag,
+            previous_value,
+            new_value,
+        })
+    }
+
+    fn undo(&self, resources: &mut Resources) {
         match self {
-            Command::PixelChanged(pixel_changed) => pixel_changed.undo(sprite_sheet),
+            Command::PixelChanged(pixel_changed) => pixel_changed.undo(resources),
+            Command::MapCellChanged(map_cell_changed) => map_cell_changed.undo(resources),
+            Command::FlagChanged(flag_changed) => flag_changed.undo(resources),
+            Command::Group(commands) => {
+                for command in commands.iter().rev() {
+                    command.undo(resources);
+                }
+            }
         }
     }
 
-    fn redo(&self, sprite_sheet: &mut SpriteSheet) {
+    fn redo(&self, resources: &mut Resources) {
         match self {
-            Command::PixelChanged(pixel_changed) => pixel_changed.redo(sprite_sheet),
+            Command::PixelChanged(pixel_changed) => pixel_changed.redo(resources),
+            Command::MapCellChanged(map_cell_changed) => map_cell_changed.redo(resources),
+            Command::FlagChanged(flag_changed) => flag_changed.redo(resources),
+            Command::Group(commands) => {
+                for command in commands {
+                    command.redo(resources);
+                }
+            }
         }
     }
 }
@@ -94,19 +183,59 @@ pub struct PixelChanged {
 }
 
 impl PixelChanged {
-    fn undo(&self, sprite_sheet: &mut SpriteSheet) {
-        let sprite = sprite_sheet.get_sprite_mut(self.sprite);
+    fn undo(&self, resources: &mut Resources) {
+        let sprite = resources.sprite_sheet.get_sprite_mut(self.sprite);
 
         sprite.pset(self.x, self.y, self.previous_color);
     }
 
-    fn redo(&self, sprite_sheet: &mut SpriteSheet) {
-        let sprite = sprite_sheet.get_sprite_mut(self.sprite);
+    fn redo(&self, resources: &mut Resources) {
+        let sprite = resources.sprite_sheet.get_sprite_mut(self.sprite);
 
         sprite.pset(self.x, self.y, self.new_color);
     }
 }
 
+#[derive(Debug)]
+pub struct MapCellChanged {
+    x: usize,
+    y: usize,
+    previous_sprite: u8,
+    new_sprite: u8,
+}
+
+impl MapCellChanged {
+    fn undo(&self, resources: &mut Resources) {
+        resources.map.mset(self.x, self.y, self.previous_sprite);
+    }
+
+    fn redo(&self, resources: &mut Resources) {
+        resources.map.mset(self.x, self.y, self.new_sprite);
+    }
+}
+
+#[derive(Debug)]
+pub struct FlagChanged {
+    sprite: usize,
+    flag: usize,
+    previous_value: bool,
+    new_value: bool,
+}
+
+impl FlagChanged {
+    fn undo(&self, resources: &mut Resources) {
+        resources
+            .sprite_flags
+            .fset(self.sprite, self.flag, self.previous_value);
+    }
+
+    fn redo(&self, resources: &mut Resources) {
+        resources
+            .sprite_flags
+            .fset(self.sprite, self.flag, self.new_value);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::editor::notification;
@@ -117,9 +246,9 @@ mod tests {
     fn undo_empty() {
         let mut commands = Commands::new();
         let mut notification = notification::State::new();
-        let mut sprite_sheet = SpriteSheet::new();
+        let mut resources = Resources::empty("");
 
-        commands.undo(&mut notification, &mut sprite_sheet);
+        commands.undo(&mut notification, &mut resources);
 
         assert_eq!(commands.current, 0);
         assert_eq!(notification.content(), "NOTHING TO UNDO");
@@ -129,9 +258,9 @@ mod tests {
     fn redo_empty() {
         let mut commands = Commands::new();
         let mut notification = notification::State::new();
-        let mut sprite_sheet = SpriteSheet::new();
+        let mut resources = Resources::empty("");
 
-        commands.redo(&mut notification, &mut sprite_sheet);
+        commands.redo(&mut notification, &mut resources);
 
         assert_eq!(commands.current, 0);
         assert_eq!(notification.content(), "NOTHING TO REDO");
@@ -141,28 +270,86 @@ mod tests {
     fn undo_many() {
         let mut commands = Commands::new();
         let mut notification = notification::State::new();
-        let mut sprite_sheet = SpriteSheet::new();
+        let mut resources = Resources::empty("");
 
         commands.push(Command::pixel_changed(20, 0, 0, 1, 2));
         commands.push(Command::pixel_changed(20, 0, 0, 2, 3));
         commands.push(Command::pixel_changed(20, 0, 0, 3, 4));
         commands.push(Command::pixel_changed(20, 0, 0, 4, 5));
-        sprite_sheet.get_sprite_mut(20).pset(0, 0, 5);
+        resources.sprite_sheet.get_sprite_mut(20).pset(0, 0, 5);
+
+        fn get_pixel(resources: &mut Resources) -> u8 {
+            resources.sprite_sheet.get_sprite_mut(20).pget(0, 0)
+        }
+
+        assert_eq!(get_pixel(&mut resources), 5);
+        commands.undo(&mut notification, &mut resources);
+        assert_eq!(get_pixel(&mut resources), 4);
+        commands.undo(&mut notification, &mut resources);
+        assert_eq!(get_pixel(&mut resources), 3);
+        commands.undo(&mut notification, &mut resources);
+        assert_eq!(get_pixel(&mut resources), 2);
+        commands.undo(&mut notification, &mut resources);
+        assert_eq!(get_pixel(&mut resources), 1);
+        commands.undo(&mut notification, &mut resources);
+        assert_eq!(get_pixel(&mut resources), 1);
+    }
+
+    #[test]
+    fn map_and_flag_edits_can_be_undone() {
+        let mut commands = Commands::new();
+        let mut notification = notification::State::new();
+        let mut resources = Resources::empty("");
+
+        resources.map.mset(3, 4, 7);
+        commands.push(Command::map_cell_changed(3, 4, 0, 7));
+        resources.sprite_flags.fset(7, 2, true);
+        commands.push(Command::flag_changed(7, 2, false, true));
+
+        commands.undo(&mut notification, &mut resources);
+        assert!(!resources.sprite_flags.fget_n(7, 2));
+        commands.undo(&mut notification, &mut resources);
+        assert_eq!(resources.map.mget(3, 4), 0);
+
+        commands.redo(&mut notification, &mut resources);
+        commands.redo(&mut notification, &mut resources);
+        assert_eq!(resources.map.mget(3, 4), 7);
+        assert!(resources.sprite_flags.fget_n(7, 2));
+    }
+
+    #[test]
+    fn groups_are_undone_as_a_whole() {
+        let mut commands = Commands::new();
+        let mut notification = notification::State::new();
+        let mut resources = Resources::empty("");
+
+        for (x, previous_color, color) in [(0, 0, 1), (1, 0, 2), (0, 1, 3)] {
+            commands.push_grouped(Command::pixel_changed(0, x, 0, previous_color, color));
+            resources.sprite_sheet.get_sprite_mut(0).pset(x, 0, color);
+        }
+        commands.close_group();
+        let pixels = |resources: &Resources| {
+            let sprite = resources.sprite_sheet.get_sprite(0);
+
+            (sprite.pget(0, 0), sprite.pget(1, 0))
+        };
+
+        commands.undo(&mut notification, &mut resources);
+        assert_eq!(pixels(&resources), (0, 0));
+        commands.redo(&mut notification, &mut resources);
+        assert_eq!(pixels(&resources), (3, 2));
+        assert_eq!(commands.commands.len(), 1);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut commands = Commands::new();
 
-        fn get_pixel(sprite_sheet: &mut SpriteSheet) -> u8 {
-            sprite_sheet.get_sprite_mut(20).pget(0, 0)
+        for _ in 0..MAX_HISTORY + 10 {
+            commands.push(Command::map_cell_changed(0, 0, 0, 1));
         }
 
-        assert_eq!(get_pixel(&mut sprite_sheet), 5);
-        commands.undo(&mut notification, &mut sprite_sheet);
-        assert_eq!(get_pixel(&mut sprite_sheet), 4);
-        commands.undo(&mut notification, &mut sprite_sheet);
-        assert_eq!(get_pixel(&mut sprite_sheet), 3);
-        commands.undo(&mut notification, &mut sprite_sheet);
-        assert_eq!(get_pixel(&mut sprite_sheet), 2);
-        commands.undo(&mut notification, &mut sprite_sheet);
-        assert_eq!(get_pixel(&mut sprite_sheet), 1);
-        commands.undo(&mut notification, &mut sprite_sheet);
-        assert_eq!(get_pixel(&mut sprite_sheet), 1);
+        assert_eq!(commands.commands.len(), MAX_HISTORY);
+        assert_eq!(commands.current, MAX_HISTORY);
     }
 }
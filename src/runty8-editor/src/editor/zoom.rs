@@ -0,0 +1,45 @@
+/// How zoomed out the map (or the sprite canvas) is: at 1/8, each sprite is a single pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Zoom {
+    Full,
+    Half,
+    Quarter,
+    Eighth,
+}
+
+impl Zoom {
+    /// Size of a sprite on the screen, in pixels.
+    pub(crate) fn tile_size(self) -> i32 {
+        match self {
+            Zoom::Full => 8,
+            Zoom::Half => 4,
+            Zoom::Quarter => 2,
+            Zoom::Eighth => 1,
+        }
+    }
+
+    pub(crate) fn zoom_in(self) -> Self {
+        match self {
+            Zoom::Full | Zoom::Half => Zoom::Full,
+            Zoom::Quarter => Zoom::Half,
+            Zoom::Eighth => Zoom::Quarter,
+        }
+    }
+
+    pub(crate) fn zoom_out(self) -> Self {
+        match self {
+            Zoom::Full => Zoom::Half,
+            Zoom::Half => Zoom::Quarter,
+            Zoom::Quarter | Zoom::Eighth => Zoom::Eighth,
+        }
+    }
+
+    pub(crate) fn to_human_readable(self) -> &'static str {
+        match self {
+            Zoom::Full => "1",
+            Zoom::Half => "1/2",
+            Zoom::Quarter => "1/4",
+            Zoom::Eighth => "1/8",
+        }
+    }
+}
@@ -31,7 +31,8 @@ pub fn run_elm_app<T: ElmApp + 'static>(resources: Resources) -> std::io::Result
 // TODO: add example
 fn run_app_compat<T: AppCompat + 'static>(resources: Resources) -> std::io::Result<()> {
     let starting_scene = start_scene();
-    implementation::run_app::<T>(starting_scene, resources);
+    let autosave = std::env::args().any(|arg| arg == "--autosave");
+    implementation::run_app::<T>(starting_scene, resources, autosave);
 
     Ok(())
 }
@@ -50,16 +51,27 @@ mod implementation {
     use crate::Resources;
     use runty8_core::Event;
     use runty8_winit::ScreenInfo;
-
-    pub(super) fn run_app<Game: AppCompat + 'static>(scene: Scene, resources: Resources) {
+    use winit::event_loop::ControlFlow;
+
+    /// With `autosave`, unsaved changes are saved when the window is closed instead of asking.
+    pub(super) fn run_app<Game: AppCompat + 'static>(
+        scene: Scene,
+        resources: Resources,
+        autosave: bool,
+    ) {
         let mut screen_info = ScreenInfo::new(640.0, 640.0);
         screen_info.scale_factor = 1.0;
 
-        let mut controller = Controller::<Game>::init(scene, resources);
+        let mut controller = Controller::<Game>::init(scene, resources, autosave);
 
         runty8_event_loop::event_loop(move |event, control_flow, draw, set_title| {
             controller.step(event);
 
+            if controller.should_exit() {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+
             if let Some(new_title) = controller.take_new_title() {
                 set_title(&new_title);
             }
@@ -1 +1,2 @@
+pub(crate) mod rect;
 pub(crate) mod vec2;
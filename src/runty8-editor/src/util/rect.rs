@@ -0,0 +1,60 @@
+use super::vec2::{vec2, Vec2i};
+
+/// A rectangle of cells (or pixels), including both corners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rect {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+}
+
+impl Rect {
+    /// The smallest rectangle containing both cells.
+    pub(crate) fn from_corners(a: Vec2i, b: Vec2i) -> Self {
+        Self {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+            width: (a.x - b.x).abs() + 1,
+            height: (a.y - b.y).abs() + 1,
+        }
+    }
+
+    pub(crate) fn top_left(self) -> Vec2i {
+        vec2(self.x, self.y)
+    }
+
+    pub(crate) fn contains(self, cell: Vec2i) -> bool {
+        (self.x..self.x + self.width).contains(&cell.x)
+            && (self.y..self.y + self.height).contains(&cell.y)
+    }
+
+    pub(crate) fn moved(self, delta: Vec2i) -> Self {
+        Self {
+            x: self.x + delta.x,
+            y: self.y + delta.y,
+            ..self
+        }
+    }
+
+    /// Every cell in the rectangle, row by row.
+    pub(crate) fn cells(self) -> impl Iterator<Item = Vec2i> {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| vec2(x, y)))
+    }
+
+    /// The part of the rectangle inside `other`, if they overlap.
+    pub(crate) fn intersection(self, other: Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+
+        (x < right && y < bottom).then_some(Rect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
+}
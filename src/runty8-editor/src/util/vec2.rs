@@ -1,6 +1,6 @@
-use std::ops::{Add, Neg, Sub};
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct Vec2<T> {
     pub x: T,
     pub y: T,
@@ -34,6 +34,28 @@ impl<T: Sub> Sub for Vec2<T> {
     }
 }
 
+impl<T: Mul + Copy> Mul<T> for Vec2<T> {
+    type Output = Vec2<T::Output>;
+
+    fn mul(self, scalar: T) -> Self::Output {
+        Vec2 {
+            x: self.x * scalar,
+            y: self.y * scalar,
+        }
+    }
+}
+
+impl<T: Div + Copy> Div<T> for Vec2<T> {
+    type Output = Vec2<T::Output>;
+
+    fn div(self, scalar: T) -> Self::Output {
+        Vec2 {
+            x: self.x / scalar,
+            y: self.y / scalar,
+        }
+    }
+}
+
 impl<T: Neg> Neg for Vec2<T> {
     type Output = Vec2<T::Output>;
 
@@ -61,6 +83,12 @@ mod tests {
         assert_eq!(vec2(4, 1234) + vec2(0, 0), vec2(4, 1234));
     }
 
+    #[test]
+    fn scalar_multiplication_works() {
+        assert_eq!(vec2(1, -2) * 8, vec2(8, -16));
+        assert_eq!(vec2(4, 1234) * -1, vec2(-4, -1234));
+    }
+
     #[test]
     fn negation_works() {
         assert_eq!(-vec2(1, 2), vec2(-1, -2));
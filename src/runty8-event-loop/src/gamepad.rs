@@ -0,0 +1,257 @@
+//! On-screen gamepad for touch screens, drawn in a panel below the game.
+
+use runty8_core::{colors, Color, Key, KeyState, KeyboardEvent, Pico8, Resources};
+use std::collections::HashMap;
+use winit::event::TouchPhase;
+
+/// A d-pad and O/X buttons that feed touches into the game as key presses.
+pub(crate) struct VirtualGamepad {
+    // Only used as a canvas to draw the gamepad on.
+    pico8: Pico8,
+    // Which key (if any) each finger on the panel is pressing.
+    touches: HashMap<u64, Option<Key>>,
+}
+
+const DPAD_CENTER: (i32, i32) = (24, 24);
+const DPAD_RADIUS: i32 = 22;
+const BUTTON_RADIUS: i32 = 9;
+// Buttons react to touches a bit outside of what's drawn, fingers aren't precise.
+const BUTTON_TOUCH_RADIUS: i32 = 14;
+const O_BUTTON: (i32, i32) = (90, 30);
+const X_BUTTON: (i32, i32) = (112, 18);
+
+impl VirtualGamepad {
+    /// Height of the gamepad panel, in Pico8 pixels.
+    pub(crate) const HEIGHT: u32 = 48;
+
+    pub(crate) fn new() -> Self {
+        let mut gamepad = Self {
+            pico8: Pico8::new(Resources::empty("")),
+            touches: HashMap::new(),
+        };
+        gamepad.render();
+
+        gamepad
+    }
+
+    /// RGB pixels of the panel, 128 pixels wide and [`Self::HEIGHT`] tall.
+    pub(crate) fn pixels(&self) -> &[u8] {
+        &self.pico8.draw_data.buffer()[..3 * 128 * Self::HEIGHT as usize]
+    }
+
+    /// Handles a touch at `position`, in Pico8 pixels relative to the top of the game.
+    ///
+    /// Returns `None` if the touch doesn't concern the gamepad (e.g, it started in the game area).
+    pub(crate) fn on_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: (i32, i32),
+    ) -> Option<Vec<KeyboardEvent>> {
+        let (x, y) = (position.0, position.1 - 128);
+
+        let events = match phase {
+            TouchPhase::Started => {
+                if y < 0 {
+                    return None;
+                }
+
+                let key = hit_test(x, y);
+                self.touches.insert(id, key);
+
+                key.map(key_down).into_iter().collect()
+            }
+            TouchPhase::Moved => {
+                let previous_key = *self.touches.get(&id)?;
+                let key = hit_test(x, y);
+
+                if previous_key == key {
+                    vec![]
+                } else {
+                    self.touches.insert(id, key);
+
+                    self.release(previous_key)
+                        .into_iter()
+                        .chain(key.map(key_down))
+                        .collect()
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let previous_key = self.touches.remove(&id)?;
+
+                self.release(previous_key).into_iter().collect()
+            }
+        };
+
+        if !events.is_empty() {
+            self.render();
+        }
+
+        Some(events)
+    }
+
+    // Keys are only released once no finger is pressing them.
+    fn release(&self, key: Option<Key>) -> Option<KeyboardEvent> {
+        let key = key?;
+
+        if self.is_pressed(key) {
+            None
+        } else {
+            Some(KeyboardEvent {
+                key,
+                state: KeyState::Up,
+            })
+        }
+    }
+
+    fn is_pressed(&self, key: Key) -> bool {
+        self.touches
+            .values()
+            .any(|touch_key| *touch_key == Some(key))
+    }
+
+    fn render(&mut self) {
+        let color = |key| {
+            if self.is_pressed(key) {
+                colors::WHITE
+            } else {
+                colors::LIGHT_GREY
+            }
+        };
+        let left = color(Key::LeftArrow);
+        let right = color(Key::RightArrow);
+        let up = color(Key::UpArrow);
+        let down = color(Key::DownArrow);
+        let o = color(Key::C);
+        let x = color(Key::X);
+
+        let pico8 = &mut self.pico8;
+        let (cx, cy) = DPAD_CENTER;
+        const ARM: i32 = 6;
+        const LENGTH: i32 = 19;
+
+        pico8.cls(colors::BLACK);
+        pico8.rectfill(cx - ARM, cy - ARM, cx + ARM, cy + ARM, colors::DARK_GREY);
+        pico8.rectfill(cx - LENGTH, cy - ARM, cx - ARM - 1, cy + ARM, left);
+        pico8.rectfill(cx + ARM + 1, cy - ARM, cx + LENGTH, cy + ARM, right);
+        pico8.rectfill(cx - ARM, cy - LENGTH, cx + ARM, cy - ARM - 1, up);
+        pico8.rectfill(cx - ARM, cy + ARM + 1, cx + ARM, cy + LENGTH, down);
+
+        draw_button(pico8, O_BUTTON, "O", o);
+        draw_button(pico8, X_BUTTON, "X", x);
+    }
+}
+
+fn draw_button(pico8: &mut Pico8, (x, y): (i32, i32), label: &str, color: Color) {
+    pico8.circfill(x, y, BUTTON_RADIUS, color);
+    pico8.print(label, x - 1, y - 2, colors::BLACK);
+}
+
+fn key_down(key: Key) -> KeyboardEvent {
+    KeyboardEvent {
+        key,
+        state: KeyState::Down,
+    }
+}
+
+/// Which key is at (x, y), in coordinates relative to the top left of the panel.
+fn hit_test(x: i32, y: i32) -> Option<Key> {
+    let distance_squared = |(cx, cy): (i32, i32)| (x - cx).pow(2) + (y - cy).pow(2);
+
+    if distance_squared(O_BUTTON) <= BUTTON_TOUCH_RADIUS.pow(2) {
+        return Some(Key::C);
+    }
+    if distance_squared(X_BUTTON) <= BUTTON_TOUCH_RADIUS.pow(2) {
+        return Some(Key::X);
+    }
+
+    if distance_squared(DPAD_CENTER) > DPAD_RADIUS.pow(2) {
+        return None;
+    }
+
+    let dx = x - DPAD_CENTER.0;
+    let dy = y - DPAD_CENTER.1;
+    if dx.abs() > dy.abs() {
+        Some(if dx < 0 {
+            Key::LeftArrow
+        } else {
+            Key::RightArrow
+        })
+    } else if dy != 0 {
+        Some(if dy < 0 { Key::UpArrow } else { Key::DownArrow })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(events: Option<Vec<KeyboardEvent>>) -> Vec<(Key, KeyState)> {
+        events
+            .unwrap()
+            .into_iter()
+            .map(|event| (event.key, event.state))
+            .collect()
+    }
+
+    #[test]
+    fn hit_test_works() {
+        assert_eq!(hit_test(8, 24), Some(Key::LeftArrow));
+        assert_eq!(hit_test(40, 26), Some(Key::RightArrow));
+        assert_eq!(hit_test(25, 8), Some(Key::UpArrow));
+        assert_eq!(hit_test(23, 40), Some(Key::DownArrow));
+        assert_eq!(hit_test(24, 24), None);
+        assert_eq!(hit_test(92, 28), Some(Key::C));
+        assert_eq!(hit_test(112, 18), Some(Key::X));
+        assert_eq!(hit_test(64, 4), None);
+    }
+
+    #[test]
+    fn touches_in_the_game_area_are_ignored() {
+        let mut gamepad = VirtualGamepad::new();
+
+        assert!(gamepad.on_touch(0, TouchPhase::Started, (64, 64)).is_none());
+        assert!(gamepad.on_touch(0, TouchPhase::Moved, (64, 140)).is_none());
+        assert!(gamepad.on_touch(0, TouchPhase::Ended, (64, 140)).is_none());
+    }
+
+    #[test]
+    fn sliding_between_directions_switches_keys() {
+        let mut gamepad = VirtualGamepad::new();
+
+        assert_eq!(
+            keys(gamepad.on_touch(3, TouchPhase::Started, (8, 128 + 24))),
+            [(Key::LeftArrow, KeyState::Down)]
+        );
+        assert_eq!(
+            keys(gamepad.on_touch(3, TouchPhase::Moved, (24, 128 + 8))),
+            [
+                (Key::LeftArrow, KeyState::Up),
+                (Key::UpArrow, KeyState::Down)
+            ]
+        );
+        assert_eq!(
+            keys(gamepad.on_touch(3, TouchPhase::Ended, (24, 128 + 8))),
+            [(Key::UpArrow, KeyState::Up)]
+        );
+    }
+
+    #[test]
+    fn keys_are_held_while_any_finger_presses_them() {
+        let mut gamepad = VirtualGamepad::new();
+
+        gamepad.on_touch(0, TouchPhase::Started, (112, 128 + 18));
+        gamepad.on_touch(1, TouchPhase::Started, (110, 128 + 20));
+
+        assert_eq!(
+            keys(gamepad.on_touch(0, TouchPhase::Ended, (112, 128 + 18))),
+            []
+        );
+        assert_eq!(
+            keys(gamepad.on_touch(1, TouchPhase::Ended, (110, 128 + 20))),
+            [(Key::X, KeyState::Up)]
+        );
+    }
+}
@@ -92,7 +92,13 @@ pub(crate) unsafe fn make_texture(gl: &Context) -> glow::Texture {
     texture
 }
 
-pub(crate) unsafe fn upload_pixels(gl: &Context, texture: glow::Texture, pixels: &[u8]) {
+pub(crate) unsafe fn upload_pixels(
+    gl: &Context,
+    texture: glow::Texture,
+    pixels: &[u8],
+    width: i32,
+    height: i32,
+) {
     gl.active_texture(glow::TEXTURE0);
 
     gl.bind_texture(glow::TEXTURE_2D, Some(texture));
@@ -101,8 +107,8 @@ pub(crate) unsafe fn upload_pixels(gl: &Context, texture: glow::Texture, pixels:
         glow::TEXTURE_2D,
         0,
         glow::RGB8 as i32,
-        128,
-        128,
+        width,
+        height,
         0,
         glow::RGB,
         glow::UNSIGNED_BYTE,
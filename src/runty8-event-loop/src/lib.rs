@@ -2,15 +2,23 @@
 
 //! Winit/Glow/Glutin powered event loop for Runty8 applications.
 
+use gamepad::VirtualGamepad;
 use glow::HasContext;
 use runty8_core::Event;
-use runty8_winit::{Runty8EventExt as _, ScreenInfo};
+use runty8_winit::{Runty8EventExt as _, ScreenInfo, TouchToMouse};
+use std::cell::RefCell;
+use std::rc::Rc;
+use touch::Touch;
 use winit::{
+    dpi::PhysicalSize,
+    event::WindowEvent,
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
 };
 
+mod gamepad;
 mod gl;
+mod touch;
 
 #[cfg(not(target_arch = "wasm32"))]
 type Window = glutin::WindowedContext<glutin::PossiblyCurrent>;
@@ -26,7 +34,14 @@ pub fn event_loop(
     #[cfg(target_arch = "wasm32")]
     wasm::setup_console_log_panic_hook();
 
+    let mut gamepad = has_touch_screen().then(VirtualGamepad::new);
+    let mut touch_to_mouse = TouchToMouse::new();
+
     let mut screen_info = ScreenInfo::new(640.0, 640.0);
+    if gamepad.is_some() {
+        screen_info.panel_height = VirtualGamepad::HEIGHT;
+        screen_info.logical_size.height *= (128 + VirtualGamepad::HEIGHT) as f64 / 128.0;
+    }
 
     let event_loop = EventLoop::new();
     let window_builder = WindowBuilder::new()
@@ -36,7 +51,12 @@ pub fn event_loop(
     let (window, gl, shader_version) = make_window_and_context(window_builder, &event_loop);
     screen_info.scale_factor = winit_window(&window).scale_factor();
 
-    let texture = unsafe {
+    // Touches waiting to be handled, in the order they happened.
+    let touches = Rc::new(RefCell::new(Vec::new()));
+    #[cfg(target_arch = "wasm32")]
+    wasm::listen_to_touches(&window, touches.clone());
+
+    let (texture, panel_texture) = unsafe {
         let vertex_array = gl
             .create_vertex_array()
             .expect("Cannot create vertex array");
@@ -47,9 +67,10 @@ pub fn event_loop(
         let program = gl::make_program(&gl, shader_version);
         gl.use_program(Some(program));
         let texture = gl::make_texture(&gl);
+        let panel_texture = gl::make_texture(&gl);
         gl::use_texture(&gl, program);
 
-        texture
+        (texture, panel_texture)
     };
 
     let mut current_time = instant::now();
@@ -58,12 +79,38 @@ pub fn event_loop(
     // => Test it
     // gl::upload_pixels(&gl, texture, pico8.draw_data.buffer());
     event_loop.run(move |winit_event, _, control_flow| {
+        if let winit::event::Event::WindowEvent {
+            event: WindowEvent::Touch(touch),
+            ..
+        } = &winit_event
+        {
+            touches.borrow_mut().push(Touch::from_winit(touch));
+        }
+
+        let touch_events: Vec<Event> = touches
+            .borrow_mut()
+            .drain(..)
+            .flat_map(|touch| {
+                touch.into_events(gamepad.as_mut(), &mut touch_to_mouse, &screen_info)
+            })
+            .collect();
+
         let event: Option<Event> =
             Event::from_winit(&winit_event, &mut current_time, &mut screen_info);
 
-        if let Some(event) = event {
+        for event in event.into_iter().chain(touch_events) {
             let draw: &dyn Fn(&[u8], &mut ControlFlow) = &|pixels, _control_flow| {
-                draw(&gl, texture, pixels);
+                let panel = gamepad
+                    .as_ref()
+                    .map(|gamepad| (panel_texture, gamepad.pixels()));
+
+                draw(
+                    &gl,
+                    texture,
+                    pixels,
+                    panel,
+                    winit_window(&window).inner_size(),
+                );
                 #[cfg(not(target_arch = "wasm32"))]
                 window.swap_buffers().unwrap();
             };
@@ -75,14 +122,54 @@ pub fn event_loop(
     })
 }
 
-fn draw(gl: &glow::Context, texture: glow::Texture, pixels: &[u8]) {
+/// Draws the game, and the panel below it (if there's one) with its texture and pixels.
+fn draw(
+    gl: &glow::Context,
+    texture: glow::Texture,
+    pixels: &[u8],
+    panel: Option<(glow::Texture, &[u8])>,
+    window_size: PhysicalSize<u32>,
+) {
     unsafe {
-        gl::upload_pixels(gl, texture, pixels);
         gl.clear(glow::COLOR_BUFFER_BIT);
-        gl.draw_arrays(glow::TRIANGLES, 0, 6);
+
+        match panel {
+            None => {
+                gl::upload_pixels(gl, texture, pixels, 128, 128);
+                gl.draw_arrays(glow::TRIANGLES, 0, 6);
+            }
+            Some((panel_texture, panel_pixels)) => {
+                let width = window_size.width as i32;
+                let panel_height = VirtualGamepad::HEIGHT as i32;
+                let panel_height_physical =
+                    window_size.height as i32 * panel_height / (128 + panel_height);
+
+                gl.viewport(
+                    0,
+                    panel_height_physical,
+                    width,
+                    window_size.height as i32 - panel_height_physical,
+                );
+                gl::upload_pixels(gl, texture, pixels, 128, 128);
+                gl.draw_arrays(glow::TRIANGLES, 0, 6);
+
+                gl.viewport(0, 0, width, panel_height_physical);
+                gl::upload_pixels(gl, panel_texture, panel_pixels, 128, panel_height);
+                gl.draw_arrays(glow::TRIANGLES, 0, 6);
+            }
+        }
     }
 }
 
+/// Whether we should show the on-screen gamepad.
+fn has_touch_screen() -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    return false;
+
+    #[cfg(target_arch = "wasm32")]
+    return wasm::has_touch_screen();
+}
+
 fn make_window_and_context(
     window_builder: WindowBuilder,
     event_loop: &EventLoop<()>,
@@ -141,7 +228,13 @@ mod native {
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
+    use crate::touch::Touch;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen::closure::Closure;
     use wasm_bindgen::JsCast;
+    use web_sys::PointerEvent;
+    use winit::dpi::LogicalPosition;
     use winit::event_loop::EventLoop;
     use winit::platform::web::WindowExtWebSys;
     use winit::window::{Window, WindowBuilder};
@@ -173,6 +266,7 @@ mod wasm {
                 image-rendering: pixelated;
                 width: 100%;
                 max-width: 600px;
+                touch-action: none;
             "#,
         );
         body.append_child(&canvas).unwrap();
@@ -187,6 +281,54 @@ mod wasm {
         glow::Context::from_webgl2_context(webgl2_context)
     }
 
+    /// Adds the touches on the `window`'s canvas to `touches`.
+    ///
+    /// winit 0.26 turns them into mouse events, so they're taken from the pointer events before
+    /// they reach the canvas (and winit): while they go down the document, in the capture phase.
+    pub(crate) fn listen_to_touches(window: &Window, touches: Rc<RefCell<Vec<Touch>>>) {
+        let canvas = window.canvas();
+        let document = web_sys::window().unwrap().document().unwrap();
+
+        let on_pointer_event = Closure::wrap(Box::new(move |event: PointerEvent| {
+            if event.target().as_ref() != Some(canvas.as_ref()) {
+                return;
+            }
+
+            let location = LogicalPosition::new(event.offset_x() as f64, event.offset_y() as f64)
+                .to_physical(web_sys::window().unwrap().device_pixel_ratio());
+            let touch = Touch::from_pointer_event(
+                &event.type_(),
+                &event.pointer_type(),
+                event.pointer_id(),
+                location,
+            );
+
+            if let Some(touch) = touch {
+                event.stop_propagation();
+                event.prevent_default();
+                touches.borrow_mut().push(touch);
+            }
+        }) as Box<dyn FnMut(PointerEvent)>);
+
+        for event_type in ["pointerdown", "pointermove", "pointerup", "pointercancel"] {
+            document
+                .add_event_listener_with_callback_and_bool(
+                    event_type,
+                    on_pointer_event.as_ref().unchecked_ref(),
+                    true,
+                )
+                .unwrap();
+        }
+        // The listener is needed for as long as the page is open.
+        on_pointer_event.forget();
+    }
+
+    pub(crate) fn has_touch_screen() -> bool {
+        let window = web_sys::window().unwrap();
+
+        window.navigator().max_touch_points() > 0
+    }
+
     pub(crate) fn set_title(title: &str) {
         let window = web_sys::window().unwrap();
         let document = window.document().unwrap();
@@ -0,0 +1,162 @@
+//! Touches, from winit on native platforms and from the canvas' pointer events on the web
+//! (where winit 0.26 turns them into mouse events, losing track of each finger).
+
+use crate::gamepad::VirtualGamepad;
+use runty8_core::{Event, InputEvent};
+use runty8_winit::{ScreenInfo, TouchToMouse};
+use winit::dpi::PhysicalPosition;
+use winit::event::TouchPhase;
+
+/// A finger touching the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Touch {
+    /// Tells apart the fingers touching the window at the same time.
+    pub(crate) id: u64,
+    pub(crate) phase: TouchPhase,
+    pub(crate) location: PhysicalPosition<f64>,
+}
+
+impl Touch {
+    pub(crate) fn from_winit(touch: &winit::event::Touch) -> Self {
+        Self {
+            id: touch.id,
+            phase: touch.phase,
+            location: touch.location,
+        }
+    }
+
+    /// The touch a browser pointer event stands for, given its `type`, `pointerType` and
+    /// `pointerId`.
+    ///
+    /// Returns `None` for other pointers (e.g, the mouse, which winit handles) and other events.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub(crate) fn from_pointer_event(
+        event_type: &str,
+        pointer_type: &str,
+        pointer_id: i32,
+        location: PhysicalPosition<f64>,
+    ) -> Option<Self> {
+        if pointer_type != "touch" {
+            return None;
+        }
+
+        let phase = match event_type {
+            "pointerdown" => TouchPhase::Started,
+            "pointermove" => TouchPhase::Moved,
+            "pointerup" => TouchPhase::Ended,
+            "pointercancel" => TouchPhase::Cancelled,
+            _ => return None,
+        };
+
+        Some(Self {
+            id: pointer_id as u64,
+            phase,
+            location,
+        })
+    }
+
+    /// Presses the virtual gamepad's buttons (if there's one, and the touch is on it),
+    /// otherwise uses the touch as the mouse.
+    pub(crate) fn into_events(
+        self,
+        gamepad: Option<&mut VirtualGamepad>,
+        touch_to_mouse: &mut TouchToMouse,
+        screen_info: &ScreenInfo,
+    ) -> Vec<Event> {
+        let position = screen_info.to_pico8_position(self.location);
+        let gamepad_events =
+            gamepad.and_then(|gamepad| gamepad.on_touch(self.id, self.phase, position));
+
+        match gamepad_events {
+            Some(keyboard_events) => keyboard_events
+                .into_iter()
+                .map(|event| Event::Input(InputEvent::Keyboard(event)))
+                .collect(),
+            None => touch_to_mouse.on_touch(self.id, self.phase, self.location, screen_info),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runty8_core::{Key, KeyState, KeyboardEvent, MouseButton, MouseEvent};
+
+    fn keys(events: Vec<Event>) -> Vec<(Key, KeyState)> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::Input(InputEvent::Keyboard(KeyboardEvent { key, state })) => (key, state),
+                event => panic!("Expected a keyboard event, got {event:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pointer_events_from_several_fingers_press_the_gamepad() {
+        let mut gamepad = VirtualGamepad::new();
+        let mut touch_to_mouse = TouchToMouse::new();
+        // 5 window pixels per Pico8 pixel, with the gamepad's panel below the game.
+        let mut screen_info = ScreenInfo::new(640.0, 640.0 + 5.0 * VirtualGamepad::HEIGHT as f64);
+        screen_info.panel_height = VirtualGamepad::HEIGHT;
+
+        let mut pointer_event = |event_type, pointer_id, (x, y): (i32, i32)| {
+            let location = PhysicalPosition::new(x as f64 * 5.0 + 2.0, y as f64 * 5.0 + 2.0);
+
+            Touch::from_pointer_event(event_type, "touch", pointer_id, location)
+                .unwrap()
+                .into_events(Some(&mut gamepad), &mut touch_to_mouse, &screen_info)
+        };
+
+        // Holding left on the d-pad while pressing X.
+        assert_eq!(
+            keys(pointer_event("pointerdown", 1, (8, 128 + 24))),
+            [(Key::LeftArrow, KeyState::Down)]
+        );
+        assert_eq!(
+            keys(pointer_event("pointerdown", 2, (112, 128 + 18))),
+            [(Key::X, KeyState::Down)]
+        );
+        assert_eq!(
+            keys(pointer_event("pointermove", 1, (24, 128 + 8))),
+            [
+                (Key::LeftArrow, KeyState::Up),
+                (Key::UpArrow, KeyState::Down)
+            ]
+        );
+        assert_eq!(
+            keys(pointer_event("pointerup", 2, (112, 128 + 18))),
+            [(Key::X, KeyState::Up)]
+        );
+        assert_eq!(
+            keys(pointer_event("pointercancel", 1, (24, 128 + 8))),
+            [(Key::UpArrow, KeyState::Up)]
+        );
+
+        // Touching the game still works as the mouse.
+        assert!(matches!(
+            pointer_event("pointerdown", 3, (64, 64))[..],
+            [
+                Event::Input(InputEvent::Mouse(MouseEvent::Move { x: 64, y: 64 })),
+                Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                    button: MouseButton::Left,
+                    state: KeyState::Down,
+                }))
+            ]
+        ));
+    }
+
+    #[test]
+    fn other_pointers_are_left_to_winit() {
+        let location = PhysicalPosition::new(0.0, 0.0);
+
+        assert_eq!(
+            Touch::from_pointer_event("pointerdown", "mouse", 1, location),
+            None
+        );
+        assert_eq!(
+            Touch::from_pointer_event("pointerover", "touch", 1, location),
+            None
+        );
+    }
+}
@@ -2,51 +2,521 @@
 
 //! Run a standalone Runty8 game natively or in wasm.
 
-use runty8_core::{App, Event, Input, Pico8, Resources};
+use runty8_core::serialize::Serialize;
+use runty8_core::{
+    srand, App, Event, FixedTimestep, Input, MenuAction, Pico8, Recording, Resources,
+};
 use runty8_event_loop::event_loop;
+use std::io::{Error, ErrorKind};
 use winit::event_loop::ControlFlow;
 
 /// Runs a standalone Runty8 game.
+///
+/// Pass `--record <file>` to the game to save the session's input to `<file>` when the window is closed,
+/// and `--replay <file>` to play such a recording back.
 pub fn run<Game: App + 'static>(resources: Resources) -> std::io::Result<()> {
-    let mut pico8 = Pico8::new(resources);
-    let mut game = Game::init(&mut pico8);
-    let mut input = Input::new();
+    let record_path = arg_value("--record");
 
-    const DELTA_TIME: f64 = 1000.0 / 30.0;
+    let mut runtime = if let Some(replay_path) = arg_value("--replay") {
+        let recording = std::fs::read_to_string(replay_path).and_then(|contents| {
+            Recording::deserialize(&contents).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+        })?;
+
+        Runtime::<Game>::replay(resources, recording)
+    } else if record_path.is_some() {
+        Runtime::<Game>::record(resources)
+    } else {
+        Runtime::<Game>::new(resources)
+    };
 
-    let mut accumulated_delta = 0.0;
     let on_event = move |event,
                          control_flow: &mut ControlFlow,
                          draw: &dyn Fn(&[u8], &mut ControlFlow),
                          set_title: &dyn Fn(&str)| {
-        if let Some(new_title) = pico8.take_new_title() {
+        if let Event::WindowClosed = event {
+            if let (Some(path), Some(recording)) = (&record_path, runtime.recording()) {
+                match std::fs::write(path, recording.serialize()) {
+                    Ok(()) => println!("Saved {} frames of input to {path}", recording.len()),
+                    Err(err) => eprintln!("Couldn't save the recording to {path}: {err}"),
+                }
+            }
+
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+
+        let new_frame = runtime.step(event);
+
+        if let Some(new_title) = runtime.take_new_title() {
             set_title(&new_title);
         }
 
+        if new_frame {
+            draw(runtime.frame_buffer(), control_flow);
+        }
+    };
+
+    event_loop(on_event);
+    Ok(())
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+
+    args.next()
+}
+
+/// A standalone Runty8 game, independent of any window or event loop.
+///
+/// Feed it [`Event`]s with [`Runtime::step`] and read what it rendered with [`Runtime::frame_buffer`].
+/// This can be used to run games headlessly (in tests, for example) or from custom front-ends.
+pub struct Runtime<Game> {
+    pico8: Pico8,
+    game: Game,
+    input: Input,
+    timestep: FixedTimestep,
+    mode: Mode,
+}
+
+enum Mode {
+    Play,
+    Record(Recording),
+    Replay { recording: Recording, frame: usize },
+}
+
+impl Mode {
+    /// Which input the game should see in the next update, if not the live one.
+    fn next_input(&mut self, live_input: &Input) -> Option<Input> {
+        match self {
+            Mode::Play => None,
+            Mode::Record(recording) => {
+                recording.record(live_input);
+
+                None
+            }
+            Mode::Replay { recording, frame } => {
+                // Once the recording is over, the player takes control.
+                let input = recording.frame(*frame);
+                *frame += 1;
+
+                input
+            }
+        }
+    }
+}
+
+impl<Game: App> Runtime<Game> {
+    /// Initializes the game, with `rnd` seeded randomly.
+    pub fn new(resources: Resources) -> Self {
+        Self::with_seed(resources, rand::random())
+    }
+
+    /// Initializes the game, seeding `rnd` with `seed` (see [`srand`]).
+    ///
+    /// Note that `rnd` is shared by everything running on the current thread.
+    pub fn with_seed(resources: Resources, seed: u64) -> Self {
+        srand(seed);
+
+        let mut pico8 = Pico8::new(resources);
+        let game = Game::init(&mut pico8);
+
+        Self {
+            pico8,
+            game,
+            input: Input::new(),
+            timestep: FixedTimestep::new(Game::FPS),
+            mode: Mode::Play,
+        }
+    }
+
+    /// Initializes the game, recording the input of every frame from now on.
+    ///
+    /// See [`Runtime::recording`].
+    pub fn record(resources: Resources) -> Self {
+        let seed = rand::random();
+        let mut runtime = Self::with_seed(resources, seed);
+        runtime.mode = Mode::Record(Recording::new(seed));
+
+        runtime
+    }
+
+    /// Initializes the game, and plays back the input from `recording` instead of the live one,
+    /// producing the same frames as the recorded session.
+    pub fn replay(resources: Resources, recording: Recording) -> Self {
+        let mut runtime = Self::with_seed(resources, recording.seed());
+        runtime.mode = Mode::Replay {
+            recording,
+            frame: 0,
+        };
+
+        runtime
+    }
+
+    /// The input recorded so far, if the game was started with [`Runtime::record`].
+    pub fn recording(&self) -> Option<&Recording> {
+        match &self.mode {
+            Mode::Record(recording) => Some(recording),
+            _ => None,
+        }
+    }
+
+    /// Handles an event, updating and drawing the game as many times as needed
+    /// if the event is an [`Event::Tick`].
+    ///
+    /// Returns whether a new frame was drawn.
+    pub fn step(&mut self, event: Event) -> bool {
         match event {
             Event::Tick { delta_millis } => {
-                accumulated_delta += delta_millis;
+                let updates = self.timestep.advance(delta_millis);
+                let tick_start = instant::Instant::now();
+                // Updates of the game itself, i.e. not counting the pause menu's.
+                let mut game_updates = 0;
+                // Measuring the time updates really take (for `stat(1)` and `stat(7)`, or even
+                // `time()` if several updates are run in one tick) would make replays differ
+                // from their recording, so each update counts as a tick that took no time instead.
+                let fixed_timing = !matches!(self.mode, Mode::Play);
+
+                for _ in 0..updates {
+                    // Paused frames are recorded too, so that replays go through the menu the same way.
+                    let replayed_input = self.mode.next_input(&self.input);
+                    self.pico8
+                        .state
+                        .update_input(replayed_input.as_ref().unwrap_or(&self.input));
+
+                    if self.pico8.state.pause_pressed() {
+                        self.pico8.toggle_pause_menu();
+                    }
 
-                while accumulated_delta > DELTA_TIME {
-                    pico8.state.update_input(&input);
+                    if self.pico8.is_paused() {
+                        if let Some(action) = self.pico8.update_pause_menu() {
+                            self.on_menu_action(action);
+                        }
 
-                    game.update(&mut pico8);
-                    game.draw(&mut pico8);
+                        continue;
+                    }
 
-                    draw(pico8.draw_data.buffer(), control_flow);
+                    self.game.update(&mut self.pico8);
+                    game_updates += 1;
 
-                    accumulated_delta -= DELTA_TIME;
+                    if fixed_timing {
+                        self.pico8
+                            .record_tick(Game::FPS, Game::FPS.delta_millis(), 1, 0.0);
+                    }
                 }
+
+                if updates > 0 {
+                    if self.pico8.is_paused() {
+                        self.pico8.draw_pause_menu();
+                    } else {
+                        self.game.draw(&mut self.pico8);
+                    }
+                }
+
+                if !fixed_timing {
+                    let busy_millis = tick_start.elapsed().as_secs_f64() * 1000.0;
+                    self.pico8
+                        .record_tick(Game::FPS, delta_millis, game_updates, busy_millis);
+                }
+
+                updates > 0
             }
             Event::Input(input_event) => {
-                input.on_event(input_event);
+                self.input.on_event(input_event);
+
+                false
             }
-            Event::WindowClosed => {
-                *control_flow = ControlFlow::Exit;
+            Event::WindowClosed => false,
+        }
+    }
+
+    fn on_menu_action(&mut self, action: MenuAction) {
+        match action {
+            MenuAction::Continue => {}
+            MenuAction::MenuItem(index) => self.game.menuitem_selected(index, &mut self.pico8),
+            MenuAction::Reset => {
+                self.pico8.reset_time();
+                self.game = Game::init(&mut self.pico8);
             }
         }
-    };
+    }
 
-    event_loop(on_event);
-    Ok(())
+    /// The last frame drawn by the game, as 128x128 RGB pixels.
+    pub fn frame_buffer(&self) -> &[u8] {
+        self.pico8.draw_data.buffer()
+    }
+
+    /// The game's title, if it changed since the last call.
+    pub fn take_new_title(&mut self) -> Option<String> {
+        self.pico8.take_new_title()
+    }
+
+    /// The Pico8 state the game runs with.
+    pub fn pico8(&self) -> &Pico8 {
+        &self.pico8
+    }
+
+    /// The game itself.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runty8_core::{Button, Fps, InputEvent, Key, KeyState, KeyboardEvent};
+
+    struct Counter {
+        updates: u32,
+        draws: u32,
+        x: i32,
+        selected_menu_item: Option<u8>,
+        // What `stat(1)`, `stat(7)` and `time()` returned in each update.
+        timings: Vec<(f32, f32, f32)>,
+    }
+
+    impl App for Counter {
+        fn init(_: &mut Pico8) -> Self {
+            Self {
+                updates: 0,
+                draws: 0,
+                x: 0,
+                selected_menu_item: None,
+                timings: vec![],
+            }
+        }
+
+        fn update(&mut self, pico8: &mut Pico8) {
+            self.updates += 1;
+            self.timings
+                .push((pico8.stat(1), pico8.stat(7), pico8.time()));
+
+            if pico8.btn(Button::Right) {
+                self.x += 1;
+            }
+        }
+
+        fn draw(&mut self, pico8: &mut Pico8) {
+            self.draws += 1;
+
+            pico8.cls(0);
+            pico8.pset(self.x, 0, 7);
+        }
+
+        fn menuitem_selected(&mut self, index: u8, _: &mut Pico8) {
+            self.selected_menu_item = Some(index);
+        }
+    }
+
+    fn tick(delta_millis: f64) -> Event {
+        Event::Tick { delta_millis }
+    }
+
+    fn press(key: Key) -> Event {
+        Event::Input(InputEvent::Keyboard(KeyboardEvent {
+            key,
+            state: KeyState::Down,
+        }))
+    }
+
+    fn release(key: Key) -> Event {
+        Event::Input(InputEvent::Keyboard(KeyboardEvent {
+            key,
+            state: KeyState::Up,
+        }))
+    }
+
+    #[test]
+    fn ticks_update_and_draw_the_game() {
+        let mut runtime = Runtime::<Counter>::new(Resources::empty(""));
+
+        assert!(!runtime.step(tick(10.0)));
+        assert!(runtime.step(tick(Fps::Thirty.delta_millis())));
+        assert_eq!(runtime.game().updates, 1);
+        assert_eq!(runtime.game().draws, 1);
+
+        // Falling behind runs several updates but only one draw.
+        assert!(runtime.step(tick(3.0 * Fps::Thirty.delta_millis())));
+        assert_eq!(runtime.game().updates, 4);
+        assert_eq!(runtime.game().draws, 2);
+    }
+
+    #[test]
+    fn time_follows_the_fixed_timestep() {
+        let mut runtime = Runtime::<Counter>::new(Resources::empty(""));
+
+        for _ in 0..45 {
+            runtime.step(tick(Fps::Thirty.delta_millis()));
+        }
+        // Slow ticks still advance time by one frame per update.
+        runtime.step(tick(5.0 * Fps::Thirty.delta_millis()));
+
+        assert_eq!(runtime.pico8().frame_count(), 45 + 4);
+        assert!((runtime.pico8().time() - 49.0 / 30.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn input_reaches_the_game() {
+        let mut runtime = Runtime::<Counter>::new(Resources::empty(""));
+
+        runtime.step(Event::Input(InputEvent::Keyboard(KeyboardEvent {
+            key: Key::RightArrow,
+            state: KeyState::Down,
+        })));
+        for _ in 0..3 {
+            runtime.step(tick(Fps::Thirty.delta_millis()));
+        }
+
+        assert_eq!(runtime.game().x, 3);
+
+        let white = [0xFF, 0xF1, 0xE8];
+        let pixel_index = 3 * 3;
+        assert_eq!(runtime.frame_buffer()[pixel_index..pixel_index + 3], white);
+    }
+
+    #[test]
+    fn pause_menu_stops_the_game_and_dispatches_menu_items() {
+        let mut runtime = Runtime::<Counter>::new(Resources::empty(""));
+        runtime.step(Event::Tick { delta_millis: 0.0 });
+        runtime.pico8.menuitem(3, Some("SECRET"));
+
+        runtime.step(press(Key::Enter));
+        runtime.step(tick(Fps::Thirty.delta_millis()));
+        assert!(runtime.pico8().is_paused());
+        runtime.step(release(Key::Enter));
+        for key in [Key::DownArrow, Key::X] {
+            runtime.step(press(key));
+            runtime.step(tick(Fps::Thirty.delta_millis()));
+            runtime.step(release(key));
+            runtime.step(tick(Fps::Thirty.delta_millis()));
+        }
+
+        assert!(!runtime.pico8().is_paused());
+        // Only the last tick, after the menu was closed, updated the game.
+        assert_eq!(runtime.game().updates, 1);
+        assert_eq!(runtime.game().selected_menu_item, Some(3));
+
+        runtime.pico8.set_pause_menu_enabled(false);
+        runtime.step(press(Key::P));
+        runtime.step(tick(Fps::Thirty.delta_millis()));
+        assert!(!runtime.pico8().is_paused());
+    }
+
+    #[test]
+    fn holding_the_pause_key_toggles_the_menu_once() {
+        let mut runtime = Runtime::<Counter>::new(Resources::empty(""));
+
+        // The OS repeats the key while it's held.
+        for _ in 0..3 {
+            runtime.step(press(Key::Enter));
+            runtime.step(tick(Fps::Thirty.delta_millis()));
+            assert!(runtime.pico8().is_paused());
+        }
+
+        runtime.step(release(Key::Enter));
+        runtime.step(tick(Fps::Thirty.delta_millis()));
+        assert!(runtime.pico8().is_paused());
+
+        runtime.step(press(Key::P));
+        runtime.step(tick(Fps::Thirty.delta_millis()));
+        assert!(!runtime.pico8().is_paused());
+    }
+
+    #[test]
+    fn replays_produce_the_same_frames() {
+        let right = |state| {
+            Event::Input(InputEvent::Keyboard(KeyboardEvent {
+                key: Key::RightArrow,
+                state,
+            }))
+        };
+        let mut recorded = Runtime::<Counter>::record(Resources::empty(""));
+
+        recorded.step(tick(Fps::Thirty.delta_millis()));
+        recorded.step(right(KeyState::Down));
+        for _ in 0..4 {
+            recorded.step(tick(Fps::Thirty.delta_millis()));
+        }
+        recorded.step(right(KeyState::Up));
+        recorded.step(tick(Fps::Thirty.delta_millis()));
+
+        let recording = recorded.recording().unwrap().clone();
+        assert_eq!(recording.len(), 6);
+
+        let mut replayed = Runtime::<Counter>::replay(Resources::empty(""), recording);
+        // Live input is ignored while replaying.
+        replayed.step(right(KeyState::Down));
+        // Ticks of different lengths don't matter, only the amount of updates.
+        replayed.step(tick(2.0 * Fps::Thirty.delta_millis()));
+        for _ in 0..4 {
+            replayed.step(tick(Fps::Thirty.delta_millis()));
+        }
+
+        assert_eq!(replayed.game().x, recorded.game().x);
+        assert_eq!(replayed.frame_buffer(), recorded.frame_buffer());
+    }
+
+    #[test]
+    fn replays_see_the_same_timings() {
+        let mut recorded = Runtime::<Counter>::record(Resources::empty(""));
+        // More than a second, so that the frame rate is measured.
+        for _ in 0..40 {
+            recorded.step(tick(2.0 * Fps::Thirty.delta_millis()));
+        }
+
+        let recording = recorded.recording().unwrap().clone();
+        let mut replayed = Runtime::<Counter>::replay(Resources::empty(""), recording);
+        for _ in 0..80 {
+            replayed.step(tick(Fps::Thirty.delta_millis()));
+        }
+
+        assert_eq!(replayed.game().timings, recorded.game().timings);
+        assert_eq!(
+            recorded.game().timings.last(),
+            Some(&(0.0, 30.0, 79.0 / 30.0))
+        );
+    }
+
+    #[test]
+    fn replays_go_through_the_pause_menu_the_same_way() {
+        let mut recorded = Runtime::<Counter>::record(Resources::empty(""));
+        recorded.pico8.menuitem(1, Some("SECRET"));
+
+        for key in [Key::Enter, Key::DownArrow, Key::X, Key::RightArrow] {
+            recorded.step(press(key));
+            recorded.step(tick(Fps::Thirty.delta_millis()));
+            recorded.step(release(key));
+            recorded.step(tick(Fps::Thirty.delta_millis()));
+        }
+        assert_eq!(recorded.game().selected_menu_item, Some(1));
+
+        let recording = recorded.recording().unwrap().clone();
+        assert_eq!(recording.len(), 8);
+
+        let mut replayed = Runtime::<Counter>::replay(Resources::empty(""), recording);
+        replayed.pico8.menuitem(1, Some("SECRET"));
+        for _ in 0..8 {
+            replayed.step(tick(Fps::Thirty.delta_millis()));
+        }
+
+        assert_eq!(replayed.game().selected_menu_item, Some(1));
+        assert_eq!(replayed.game().updates, recorded.game().updates);
+        assert_eq!(replayed.game().x, recorded.game().x);
+        assert_eq!(replayed.frame_buffer(), recorded.frame_buffer());
+    }
+
+    #[test]
+    fn same_seed_generates_the_same_random_numbers() {
+        let first: Vec<f32> = {
+            let _runtime = Runtime::<Counter>::with_seed(Resources::empty(""), 7);
+            (0..5).map(|_| runty8_core::rnd(1.0)).collect()
+        };
+        let second: Vec<f32> = {
+            let _runtime = Runtime::<Counter>::with_seed(Resources::empty(""), 7);
+            (0..5).map(|_| runty8_core::rnd(1.0)).collect()
+        };
+
+        assert_eq!(first, second);
+    }
 }
@@ -0,0 +1,225 @@
+#![deny(missing_docs)]
+
+//! Headless test harness for Runty8 games.
+//!
+//! Runs a game for a number of frames with scripted input,
+//! and compares what it draws against golden images stored as PPM files.
+//!
+//! ```no_run
+//! # use runty8_core::{App, Button, Pico8, Resources};
+//! # use runty8_test::TestRunner;
+//! # struct MyGame;
+//! # impl App for MyGame {
+//! #     fn init(_: &mut Pico8) -> Self { MyGame }
+//! #     fn update(&mut self, _: &mut Pico8) {}
+//! #     fn draw(&mut self, _: &mut Pico8) {}
+//! # }
+//! # fn resources() -> Resources { todo!() }
+//! TestRunner::<MyGame>::new(Resources::empty(""))
+//!     .run_frames(30)
+//!     .press(Button::X)
+//!     .run_frames(2)
+//!     .release(Button::X)
+//!     .assert_snapshot("snapshots/after_jump.ppm");
+//! ```
+
+mod snapshot;
+
+use runty8_core::serialize::Ppm;
+use runty8_core::{
+    App, Button, Event, InputEvent, Key, KeyState, KeyboardEvent, MouseButton, MouseEvent,
+    Recording, Resources,
+};
+use runty8_runtime::Runtime;
+use std::path::Path;
+
+pub use snapshot::UPDATE_SNAPSHOTS_ENV_VAR;
+
+/// Runs a game headlessly, one frame at a time.
+pub struct TestRunner<Game> {
+    runtime: Runtime<Game>,
+    frames: u32,
+}
+
+impl<Game: App> TestRunner<Game> {
+    /// Initializes the game, with `rnd` always seeded the same way so that tests are deterministic.
+    pub fn new(resources: Resources) -> Self {
+        Self {
+            runtime: Runtime::with_seed(resources, 0),
+            frames: 0,
+        }
+    }
+
+    /// Initializes the game to play back `recording` (e.g, one saved with `--record`),
+    /// see [`TestRunner::run_frames`].
+    pub fn replay(resources: Resources, recording: Recording) -> Self {
+        Self {
+            runtime: Runtime::replay(resources, recording),
+            frames: 0,
+        }
+    }
+
+    /// Starts holding a button, it'll be seen by the game from the next frame on.
+    pub fn press(&mut self, button: Button) -> &mut Self {
+        self.runtime
+            .step(Event::Input(button_event(button, KeyState::Down)));
+
+        self
+    }
+
+    /// Stops holding a button.
+    pub fn release(&mut self, button: Button) -> &mut Self {
+        self.runtime
+            .step(Event::Input(button_event(button, KeyState::Up)));
+
+        self
+    }
+
+    /// Moves the mouse to (x, y), in screen pixels.
+    pub fn move_mouse(&mut self, x: i32, y: i32) -> &mut Self {
+        self.runtime
+            .step(Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y })));
+
+        self
+    }
+
+    /// Updates and draws the game `frames` times.
+    pub fn run_frames(&mut self, frames: u32) -> &mut Self {
+        let delta_millis = Game::FPS.delta_millis();
+
+        for _ in 0..frames {
+            let drew_frame = self.runtime.step(Event::Tick { delta_millis });
+            debug_assert!(drew_frame);
+        }
+        self.frames += frames;
+
+        self
+    }
+
+    /// How many frames have been run so far.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// The game itself, to check its state.
+    pub fn game(&self) -> &Game {
+        self.runtime.game()
+    }
+
+    /// The last frame drawn by the game, as 128x128 RGB pixels.
+    pub fn frame_buffer(&self) -> &[u8] {
+        self.runtime.frame_buffer()
+    }
+
+    /// The last frame drawn by the game, as an image.
+    pub fn screenshot(&self) -> Ppm {
+        Ppm::from_rgb(128, 128, self.frame_buffer())
+    }
+
+    /// Compares the last frame drawn with the golden image at `path`.
+    ///
+    /// Missing golden images are an error. Set the [`UPDATE_SNAPSHOTS_ENV_VAR`] environment
+    /// variable to save the current frame at `path` instead (creating or replacing it).
+    ///
+    /// On mismatch, the current frame and an image highlighting the differing pixels
+    /// are saved next to the golden image, as `<name>.actual.ppm` and `<name>.diff.ppm`.
+    pub fn check_snapshot(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        snapshot::check(path.as_ref(), &self.screenshot())
+    }
+
+    /// Like [`TestRunner::check_snapshot`], but panics on mismatch.
+    pub fn assert_snapshot(&self, path: impl AsRef<Path>) -> &Self {
+        if let Err(error) = self.check_snapshot(path) {
+            panic!("Snapshot mismatch after {} frames: {error}", self.frames);
+        }
+
+        self
+    }
+}
+
+fn button_event(button: Button, state: KeyState) -> InputEvent {
+    let key = match button {
+        Button::Left => Key::LeftArrow,
+        Button::Right => Key::RightArrow,
+        Button::Up => Key::UpArrow,
+        Button::Down => Key::DownArrow,
+        Button::X => Key::X,
+        Button::C => Key::C,
+        Button::Mouse => return mouse_button_event(MouseButton::Left, state),
+        Button::MouseRight => return mouse_button_event(MouseButton::Right, state),
+        Button::MouseMiddle => return mouse_button_event(MouseButton::Middle, state),
+    };
+
+    InputEvent::Keyboard(KeyboardEvent { key, state })
+}
+
+fn mouse_button_event(button: MouseButton, state: KeyState) -> InputEvent {
+    InputEvent::Mouse(MouseEvent::Button { button, state })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runty8_core::serialize::Serialize;
+    use runty8_core::Pico8;
+
+    struct Walker {
+        x: i32,
+    }
+
+    impl App for Walker {
+        fn init(_: &mut Pico8) -> Self {
+            Self { x: 10 }
+        }
+
+        fn update(&mut self, pico8: &mut Pico8) {
+            if pico8.btn(Button::Right) {
+                self.x += 1;
+            }
+        }
+
+        fn draw(&mut self, pico8: &mut Pico8) {
+            pico8.cls(1);
+            pico8.rectfill(self.x, 60, self.x + 7, 67, 8);
+        }
+    }
+
+    #[test]
+    fn scripted_input_reaches_the_game() {
+        let mut runner = TestRunner::<Walker>::new(Resources::empty(""));
+
+        runner
+            .run_frames(5)
+            .press(Button::Right)
+            .run_frames(3)
+            .release(Button::Right)
+            .run_frames(5);
+
+        assert_eq!(runner.game().x, 13);
+        assert_eq!(runner.frames(), 13);
+    }
+
+    #[test]
+    fn snapshots_detect_changes() {
+        let directory = std::env::temp_dir().join(format!("runty8-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("walker.ppm");
+
+        let mut runner = TestRunner::<Walker>::new(Resources::empty(""));
+        runner.run_frames(1);
+        let error = runner.check_snapshot(&path).unwrap_err();
+        assert!(error.contains(UPDATE_SNAPSHOTS_ENV_VAR), "{error}");
+
+        std::fs::write(&path, runner.screenshot().serialize()).unwrap();
+        runner.assert_snapshot(&path);
+
+        runner.press(Button::Right).run_frames(2);
+        let error = runner.check_snapshot(&path).unwrap_err();
+
+        assert!(error.contains("32 pixels differ"), "{error}");
+        assert!(directory.join("walker.actual.ppm").exists());
+        assert!(directory.join("walker.diff.ppm").exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
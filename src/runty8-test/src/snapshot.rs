@@ -0,0 +1,84 @@
+use runty8_core::serialize::{Ppm, Serialize};
+use std::path::Path;
+
+/// Set this environment variable to overwrite golden images with the current frames.
+pub const UPDATE_SNAPSHOTS_ENV_VAR: &str = "RUNTY8_UPDATE_SNAPSHOTS";
+
+// Pico8's red, for pixels that don't match.
+const DIFF_COLOR: [u8; 3] = [0xFF, 0x00, 0x4D];
+
+pub(crate) fn check(path: &Path, actual: &Ppm) -> Result<(), String> {
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV_VAR).is_some() {
+        return write(path, actual);
+    }
+
+    if !path.exists() {
+        return Err(format!(
+            "{} doesn't exist, set {UPDATE_SNAPSHOTS_ENV_VAR} to create it",
+            path.display()
+        ));
+    }
+
+    let expected = std::fs::read_to_string(path)
+        .map_err(|err| format!("Couldn't read {}: {err}", path.display()))
+        .and_then(|contents| Ppm::deserialize(&contents))?;
+
+    if expected == *actual {
+        return Ok(());
+    }
+
+    let actual_path = path.with_extension("actual.ppm");
+    write(&actual_path, actual)?;
+
+    if (expected.width(), expected.height()) != (actual.width(), actual.height()) {
+        return Err(format!(
+            "expected a {}x{} image, got {}x{}, see {}",
+            expected.width(),
+            expected.height(),
+            actual.width(),
+            actual.height(),
+            actual_path.display()
+        ));
+    }
+
+    let (differing_pixels, diff) = diff(&expected, actual);
+    let diff_path = path.with_extension("diff.ppm");
+    write(&diff_path, &diff)?;
+
+    Err(format!(
+        "{differing_pixels} pixels differ from {}, see {} and {}",
+        path.display(),
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+/// Returns how many pixels differ, and an image with the expected image dimmed
+/// and the differing pixels highlighted.
+fn diff(expected: &Ppm, actual: &Ppm) -> (usize, Ppm) {
+    let mut differing_pixels = 0;
+
+    let pixels: Vec<u8> = expected
+        .to_rgb()
+        .chunks_exact(3)
+        .zip(actual.to_rgb().chunks_exact(3))
+        .flat_map(|(expected, actual)| {
+            if expected == actual {
+                [expected[0] / 3, expected[1] / 3, expected[2] / 3]
+            } else {
+                differing_pixels += 1;
+                DIFF_COLOR
+            }
+        })
+        .collect();
+
+    (
+        differing_pixels,
+        Ppm::from_rgb(expected.width(), expected.height(), &pixels),
+    )
+}
+
+fn write(path: &Path, image: &Ppm) -> Result<(), String> {
+    std::fs::write(path, image.serialize())
+        .map_err(|err| format!("Couldn't write {}: {err}", path.display()))
+}
@@ -3,7 +3,8 @@
 //! Compatibility layer for Runty8 crates that need to leverage winit.
 
 use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent, MouseButton, MouseEvent};
-use winit::dpi::{LogicalPosition, LogicalSize};
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition};
+use winit::event::TouchPhase;
 
 /// Information about the current viewport for rendering.
 pub struct ScreenInfo {
@@ -11,6 +12,8 @@ pub struct ScreenInfo {
     pub scale_factor: f64,
     /// Display resolution in "logical" units.
     pub logical_size: LogicalSize<f64>,
+    /// Height (in Pico8 pixels) of the panel drawn below the 128x128 game, if any.
+    pub panel_height: u32,
 }
 
 impl ScreenInfo {
@@ -19,6 +22,69 @@ impl ScreenInfo {
         Self {
             scale_factor: 1.0,
             logical_size: LogicalSize::new(width, height),
+            panel_height: 0,
+        }
+    }
+
+    /// Converts a position in the window into Pico8 pixel coordinates.
+    ///
+    /// Positions in the panel below the game have `y >= 128`.
+    pub fn to_pico8_position(&self, position: PhysicalPosition<f64>) -> (i32, i32) {
+        let logical_position: LogicalPosition<f64> = position.to_logical(self.scale_factor);
+        let window_size = self.logical_size;
+        let height_pixels = 128.0 + self.panel_height as f64;
+
+        (
+            (logical_position.x / window_size.width * 128.).floor() as i32,
+            (logical_position.y / window_size.height * height_pixels).floor() as i32,
+        )
+    }
+}
+
+/// Translates touches into mouse events, so that games can be played on touch screens.
+///
+/// Only the first finger to touch the screen controls the mouse.
+#[derive(Debug, Default)]
+pub struct TouchToMouse {
+    finger: Option<u64>,
+}
+
+impl TouchToMouse {
+    /// Creates a translator with no fingers on the screen.
+    pub fn new() -> Self {
+        Self { finger: None }
+    }
+
+    /// Mouse events equivalent to the finger `id` touching the window at `location`.
+    pub fn on_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        location: PhysicalPosition<f64>,
+        screen_info: &ScreenInfo,
+    ) -> Vec<Event> {
+        let (x, y) = screen_info.to_pico8_position(location);
+        let move_event = Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y }));
+        let left_button = |state| {
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state,
+            }))
+        };
+
+        match phase {
+            TouchPhase::Started if self.finger.is_none() => {
+                self.finger = Some(id);
+
+                vec![move_event, left_button(KeyState::Down)]
+            }
+            TouchPhase::Moved if self.finger == Some(id) => vec![move_event],
+            TouchPhase::Ended | TouchPhase::Cancelled if self.finger == Some(id) => {
+                self.finger = None;
+
+                vec![move_event, left_button(KeyState::Up)]
+            }
+            _ => vec![],
         }
     }
 }
@@ -55,18 +121,13 @@ impl Runty8EventExt for Event {
                     screen_info.scale_factor = scale_factor;
                     None
                 }
-                winit::event::WindowEvent::CursorMoved { position, .. } => {
-                    let logical_mouse: LogicalPosition<f64> =
-                        position.to_logical(screen_info.scale_factor);
-
-                    let window_size = screen_info.logical_size;
-                    Some(Event::Input(InputEvent::Mouse(MouseEvent::Move {
-                        x: (logical_mouse.x / window_size.width * 128.).floor() as i32,
-                        y: (logical_mouse.y / window_size.height * 128.).floor() as i32,
-                    })))
+                &winit::event::WindowEvent::CursorMoved { position, .. } => {
+                    let (x, y) = screen_info.to_pico8_position(position);
+
+                    Some(Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y })))
                 }
                 winit::event::WindowEvent::MouseInput {
-                    button: winit::event::MouseButton::Left,
+                    button,
                     state: input_state,
                     ..
                 } => {
@@ -76,11 +137,31 @@ impl Runty8EventExt for Event {
                     };
 
                     let mouse_event = MouseEvent::Button {
-                        button: MouseButton::Left,
+                        button: MouseButton::from_winit(*button)?,
                         state: mouse_button_state,
                     };
                     Some(Event::Input(InputEvent::Mouse(mouse_event)))
                 }
+                winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                    let (delta_x, delta_y) = match *delta {
+                        winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                            (x.round() as i32, y.round() as i32)
+                        }
+                        // Pixel deltas vary wildly between devices, so we only keep the direction.
+                        winit::event::MouseScrollDelta::PixelDelta(position) => {
+                            (scroll_direction(position.x), scroll_direction(position.y))
+                        }
+                    };
+
+                    if delta_x == 0 && delta_y == 0 {
+                        None
+                    } else {
+                        Some(Event::Input(InputEvent::Mouse(MouseEvent::Wheel {
+                            delta_x,
+                            delta_y,
+                        })))
+                    }
+                }
                 winit::event::WindowEvent::KeyboardInput { input, .. } => {
                     KeyboardEvent::from_winit(*input)
                         .map(InputEvent::Keyboard)
@@ -104,6 +185,17 @@ impl Runty8EventExt for Event {
         }
     }
 }
+
+fn scroll_direction(delta: f64) -> i32 {
+    if delta > 0.0 {
+        1
+    } else if delta < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
 trait Runty8KeyboardEventExt: Sized {
     fn from_winit(input: winit::event::KeyboardInput) -> Option<Self>;
 }
@@ -164,11 +256,31 @@ impl Runty8KeyExt for Key {
             VirtualKeyCode::Escape => Some(Self::Escape),
             VirtualKeyCode::LAlt => Some(Self::Alt),
             VirtualKeyCode::Space => Some(Self::Space),
+            VirtualKeyCode::Return => Some(Self::Enter),
+            VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => Some(Self::Minus),
+            VirtualKeyCode::Equals | VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => {
+                Some(Self::Equals)
+            }
             _ => None,
         }
     }
 }
 
+trait Runty8MouseButtonExt: Sized {
+    fn from_winit(button: winit::event::MouseButton) -> Option<Self>;
+}
+
+impl Runty8MouseButtonExt for MouseButton {
+    fn from_winit(button: winit::event::MouseButton) -> Option<Self> {
+        match button {
+            winit::event::MouseButton::Left => Some(Self::Left),
+            winit::event::MouseButton::Right => Some(Self::Right),
+            winit::event::MouseButton::Middle => Some(Self::Middle),
+            winit::event::MouseButton::Other(_) => None,
+        }
+    }
+}
+
 trait Runty8KeyStateExt: Sized {
     fn from_state(state: winit::event::ElementState) -> Self;
 }